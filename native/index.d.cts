@@ -0,0 +1,303 @@
+// Generated from the Rust types by `UPDATE_TYPES=1 cargo test` in native/, do not edit.
+
+export type Span = { start: number, end: number, line: number, column: number, };
+
+export type SimpleNode = { "List": { ordered: boolean, items: Array<Array<SimpleNode>>, span: Span, } } | { "DefinitionList": { items: Array<SimpleDefinitionItem>, span: Span, } } | { "Template": SimpleTemplate } | { "Link": { target: string, text: string, span: Span, } } | { "ExternalLink": { url: string, text: Array<SimpleNode>, span: Span, } } | { "Image": { target: string, text: Array<SimpleNode>, span: Span, } } | { "Category": { target: string, sortKey: string | null, span: Span, } } | { "Text": { value: string, span: Span, } } | { "Bold": { span: Span, } } | { "Italic": { span: Span, } } | { "BoldItalic": { span: Span, } } | { "Heading": { level: number, nodes: Array<SimpleNode>, span: Span, } } | { "Tag": { name: string, attributes: string, nodes: Array<SimpleNode>, span: Span, } } | { "StartTag": { name: string, attributes: string, span: Span, } } | { "EndTag": { name: string, span: Span, } } | { "Table": SimpleTable } | { "Preformatted": { nodes: Array<SimpleNode>, span: Span, } } | { "Parameter": { name: string, default: Array<SimpleNode> | null, span: Span, } } | { "MagicWord": { name: string, span: Span, } } | { "Redirect": { target: string, span: Span, } } | { "Comment": { text: string, span: Span, } } | { "HorizontalDivider": { span: Span, } } | { "ParagraphBreak": { span: Span, } };
+
+export type SimpleTemplate = { name: string, parameters: Array<SimpleParameter>, span: Span, };
+
+export type SimpleParameter = { name: string | null, value: Array<SimpleNode>, span: Span, };
+
+export type SimpleDefinitionItem = { 
+/**
+ * `;` term, otherwise `:` details.
+ */
+term: boolean, nodes: Array<SimpleNode>, span: Span, };
+
+export type SimpleTable = { attributes: string, captions: Array<Array<SimpleNode>>, rows: Array<SimpleTableRow>, span: Span, };
+
+export type SimpleTableRow = { attributes: string, cells: Array<SimpleTableCell>, span: Span, };
+
+export type SimpleTableCell = { 
+/**
+ * `!` cell, otherwise `|`.
+ */
+heading: boolean, attributes: string | null, content: Array<SimpleNode>, span: Span, };
+
+export type DiagnosticKind = "DefinitionTermContinuation" | "EndTagInComment" | "InvalidCharacter" | "InvalidHeadingSyntaxRewinding" | "InvalidLinkSyntax" | "InvalidParameterSyntax" | "InvalidTagSyntax" | "MissingEndTagRewinding" | "RepeatedEmptyLine" | "StrayTextInTable" | "TextAfterRedirect" | "UnexpectedEndTagRewinding" | "UnexpectedEndTag" | "UnexpectedHeadingLevelCorrecting" | "UnrecognizedTagName" | "UselessTextInParameter" | "UselessTextInRedirect";
+
+export type Diagnostic = { kind: DiagnosticKind, message: string, span: Span, 
+/**
+ * The offending wikitext.
+ */
+snippet: string, 
+/**
+ * The offending wikitext with up to `CONTEXT_RADIUS` bytes on each side.
+ */
+context: string, };
+
+export type ParsedWikitext = { nodes: Array<SimpleNode>, diagnostics: Array<Diagnostic>, };
+
+export type Constructs = { 
+/**
+ * Bold and italic toggles.
+ */
+formatting: boolean, externalLinks: boolean, images: boolean, categories: boolean, headings: boolean, definitionLists: boolean, 
+/**
+ * Tags like `<ref>`, and unpaired start and end tags.
+ */
+tags: boolean, tables: boolean, preformatted: boolean, 
+/**
+ * Template parameters like `{{{1}}}`.
+ */
+parameters: boolean, magicWords: boolean, redirects: boolean, 
+/**
+ * Comments are otherwise removed before parsing, along with the line they stand on.
+ */
+comments: boolean, 
+/**
+ * Paragraph breaks and horizontal dividers.
+ */
+breaks: boolean, };
+
+export type ParseOptions = { 
+/**
+ * Fail on any diagnostic instead of returning the diagnostics next to the result.
+ */
+strict: boolean, 
+/**
+ * Parse the wikitext of each template parameter again on its own, since lists and other
+ * constructs at the start of a line aren't found in parameters otherwise.
+ */
+reparseParameters: boolean, 
+/**
+ * `true` for all of them, or the flags of the constructs to keep.
+ */
+constructs: boolean | Partial<Constructs>, 
+/**
+ * Names of the appearances templates to accept. `parse_appearances` accepts any template
+ * and `parse_article_appearances` looks for `{{App}}` when missing.
+ */
+templateNames: Array<string> | null, 
+/**
+ * Merged over the default aliases.
+ */
+categoryAliases: CategoryAliases, deduplicate: boolean, 
+/**
+ * Format the nodes of the result are serialized in, see `with_node_format`.
+ */
+format: NodeFormat, };
+
+export type Settled<T> = { "status": "fulfilled", value: T, } | { "status": "rejected", reason: string, };
+
+export type AppearanceKind = "link" | "unlinked";
+
+export type Qualifier = { "type": "firstAppearance" } | { "type": "firstIdentified", name: string | null, } | { "type": "mentionedOnly" } | { "type": "indirectMention" } | { "type": "flashback" } | { "type": "hologram" } | { "type": "vision" } | { "type": "comment", text: string, };
+
+export type Appearance = { kind: AppearanceKind, 
+/**
+ * Normalized link target, e.g. `Coruscant/Legends#Senate District`.
+ */
+name: string, 
+/**
+ * Page title without the anchor and the `/Legends` suffix, e.g. `Coruscant`.
+ */
+title: string, anchor: string | null, 
+/**
+ * Text shown for the link, e.g. `1138` for `[[1138 (Geonosis)|1138]]`.
+ */
+text: string, 
+/**
+ * Whether the link points to the Legends version of the page.
+ */
+legends: boolean, templates: Array<SimpleTemplate> | null, 
+/**
+ * Known templates from `templates`, e.g. `{{1st}}`.
+ */
+qualifiers: Array<Qualifier>, 
+/**
+ * Templates from `templates` that aren't qualifiers.
+ */
+unknownTemplates: Array<SimpleTemplate>, span: Span, 
+/**
+ * Name of the appearance this one is listed under, e.g. the war of a battle.
+ */
+parent: string | null, 
+/**
+ * How many times the appearance is listed in its category, 1 unless deduplicated.
+ */
+occurrences: number, };
+
+export type AppearanceTree = { children: Array<AppearanceTree>, kind: AppearanceKind, 
+/**
+ * Normalized link target, e.g. `Coruscant/Legends#Senate District`.
+ */
+name: string, 
+/**
+ * Page title without the anchor and the `/Legends` suffix, e.g. `Coruscant`.
+ */
+title: string, anchor: string | null, 
+/**
+ * Text shown for the link, e.g. `1138` for `[[1138 (Geonosis)|1138]]`.
+ */
+text: string, 
+/**
+ * Whether the link points to the Legends version of the page.
+ */
+legends: boolean, templates: Array<SimpleTemplate> | null, 
+/**
+ * Known templates from `templates`, e.g. `{{1st}}`.
+ */
+qualifiers: Array<Qualifier>, 
+/**
+ * Templates from `templates` that aren't qualifiers.
+ */
+unknownTemplates: Array<SimpleTemplate>, span: Span, 
+/**
+ * Name of the appearance this one is listed under, e.g. the war of a battle.
+ */
+parent: string | null, 
+/**
+ * How many times the appearance is listed in its category, 1 unless deduplicated.
+ */
+occurrences: number, };
+
+export type ContinuityAppearances = { canon: { [key in string]?: Array<Appearance> }, legends: { [key in string]?: Array<Appearance> }, unprefixed: { [key in string]?: Array<Appearance> }, };
+
+export type CategoryAliases = { [key in string]?: string };
+
+export type Appearances = { nodes: Array<SimpleNode>, 
+/**
+ * Appearances by category, each listed once per target when deduplicated.
+ */
+links: { [key in string]?: Array<Appearance> }, 
+/**
+ * Same appearances as `links`, nested the way the wikitext lists are.
+ */
+tree: { [key in string]?: Array<AppearanceTree> }, 
+/**
+ * Same appearances as `links`, by continuity and canonical category name.
+ */
+continuities: ContinuityAppearances, diagnostics: Array<Diagnostic>, };
+
+export type TemplateAppearances = { links: { [key in string]?: Array<Appearance> }, tree: { [key in string]?: Array<AppearanceTree> }, continuities: ContinuityAppearances, };
+
+export type SectionAppearances = { 
+/**
+ * `path` joined with ` > `, unique within the article: later templates under the same
+ * headings get ` #2`, ` #3` and so on. Empty before the first heading.
+ */
+key: string, 
+/**
+ * Nearest heading above the template, `None` before the first heading.
+ */
+heading: string | null, 
+/**
+ * Headings enclosing the template, outermost first, e.g. the story of an anthology.
+ */
+path: Array<string>, template: SimpleTemplate, appearances: TemplateAppearances, };
+
+export type ArticleAppearances = { 
+/**
+ * In article order. A list rather than a map by heading, since an article can have
+ * templates before its first heading and under repeated headings, like the `Appearances`
+ * of each story in an anthology. Look them up by `key` with `section`.
+ */
+sections: Array<SectionAppearances>, diagnostics: Array<Diagnostic>, };
+
+export type FieldSpec = string | { name: string | null, aliases: Array<string>, 
+/**
+ * Rich text fields are stored under `<key>Details`.
+ */
+details: boolean, };
+
+export type InfoboxValue = { 
+/**
+ * Infobox parameter the value was taken from.
+ */
+alias: string, text: string, 
+/**
+ * Text of each item, when the value is a bulleted list.
+ */
+items: Array<string> | null, 
+/**
+ * Normalized targets of all links in the value.
+ */
+links: Array<string>, nodes: Array<SimpleNode>, span: Span, };
+
+export type Infobox = { template: string, fields: { [key in string]?: InfoboxValue }, span: Span, };
+
+export type ParsedInfobox = { infobox: Infobox | null, diagnostics: Array<Diagnostic>, };
+
+export type ParamKey = number | string;
+
+export type ParamRef = ParamKey | { param: ParamKey, 
+/**
+ * Drops a trailing disambiguation like ` (novel)`, as `{{HideParanthetical}}` does for page names.
+ */
+hideParenthetical: boolean, };
+
+export type TemplateRule = { "rule": "param", params: Array<ParamRef>, 
+/**
+ * Parameters naming the page the template links to, e.g. `story` of `{{StoryCite}}`.
+ */
+link: Array<ParamKey>, } | { "rule": "literal", text: string, } | { "rule": "drop" };
+
+export type TextRules = { [key in string]?: TemplateRule };
+
+export type TimelineRow = { 
+/**
+ * In-universe date, e.g. `19 BBY`.
+ */
+year: string, 
+/**
+ * Media type code from the unnamed column, e.g. `N` or `TV`.
+ */
+typeCode: string, 
+/**
+ * Page the title links to, from its first link or citation template, without the anchor.
+ */
+titleLink: string | null, 
+/**
+ * Text of the title cell before the notes, without the `†` flag.
+ */
+titleText: string, titleNodes: Array<SimpleNode>, 
+/**
+ * Notes following `*` in the title cell, e.g. `Adaptation`.
+ */
+notes: Array<string>, exactPlacementUnknown: boolean, releaseDate: string, span: Span, };
+
+export type ParsedTimeline = { rows: Array<TimelineRow>, diagnostics: Array<Diagnostic>, };
+
+export type QueryMatch = SimpleNode | SimpleParameter;
+
+export type QueryOutput = { matches: Array<QueryMatch>, diagnostics: Array<Diagnostic>, };
+
+export type WikiConfig = { categoryNamespaces: Array<string>, extensionTags: Array<string>, fileNamespaces: Array<string>, linkTrail: string, magicWords: Array<string>, protocols: Array<string>, redirectMagicWords: Array<string>, };
+
+export type NodeFormat = "external" | "tagged";
+
+export type TaggedNode = { "type": "list", ordered: boolean, data: Array<Array<TaggedNode>>, span: Span, } | { "type": "definition list", items: Array<Tagged<SimpleDefinitionItem>>, span: Span, } | { "type": "template", name: string, parameters: Array<Tagged<SimpleParameter>>, span: Span, } | { "type": "internal link", page: string, text: string, span: Span, } | { "type": "external link", site: string, text: string, nodes: Array<TaggedNode>, span: Span, } | { "type": "image", target: string, text: Array<TaggedNode>, span: Span, } | { "type": "category", target: string, sortKey: string | null, span: Span, } | { "type": "text", text: string, span: Span, } | { "type": "bold", span: Span, } | { "type": "italic", span: Span, } | { "type": "bold italic", span: Span, } | { "type": "heading", level: number, nodes: Array<TaggedNode>, span: Span, } | { "type": "tag", name: string, attributes: string, nodes: Array<TaggedNode>, span: Span, } | { "type": "start tag", name: string, attributes: string, span: Span, } | { "type": "end tag", name: string, span: Span, } | { "type": "table", attributes: string, captions: Array<Array<TaggedNode>>, rows: Array<Tagged<SimpleTableRow>>, span: Span, } | { "type": "preformatted", nodes: Array<TaggedNode>, span: Span, } | { "type": "parameter", name: string, default: Array<TaggedNode> | null, span: Span, } | { "type": "magic word", name: string, span: Span, } | { "type": "redirect", target: string, span: Span, } | { "type": "comment", text: string, span: Span, } | { "type": "horizontal divider", span: Span, } | { "type": "paragraph break", span: Span, };
+
+export type Tagged<T> = T extends SimpleNode ? TaggedNode : T extends Array<infer U> ? Array<Tagged<U>> : T extends object ? { [K in keyof T]: Tagged<T[K]> } : T;
+
+export function parse(wikitext: string, options?: Partial<ParseOptions> & { format?: "external" }): ParsedWikitext;
+export function parse(wikitext: string, options: Partial<ParseOptions> & { format: "tagged" }): Tagged<ParsedWikitext>;
+export function parse_baseline(wikitext: string, options?: Partial<ParseOptions> & { format?: "external" }): ParsedWikitext;
+export function parse_baseline(wikitext: string, options: Partial<ParseOptions> & { format: "tagged" }): Tagged<ParsedWikitext>;
+export function parse_appearances(wikitext: string, options?: Partial<ParseOptions> & { format?: "external" }): Appearances;
+export function parse_appearances(wikitext: string, options: Partial<ParseOptions> & { format: "tagged" }): Tagged<Appearances>;
+export function parse_many(wikitexts: string[], options?: Partial<ParseOptions> & { format?: "external" }): Promise<Settled<ParsedWikitext>[]>;
+export function parse_many(wikitexts: string[], options: Partial<ParseOptions> & { format: "tagged" }): Promise<Settled<Tagged<ParsedWikitext>>[]>;
+export function parse_appearances_many(wikitexts: string[], options?: Partial<ParseOptions> & { format?: "external" }): Promise<Settled<Appearances>[]>;
+export function parse_appearances_many(wikitexts: string[], options: Partial<ParseOptions> & { format: "tagged" }): Promise<Settled<Tagged<Appearances>>[]>;
+export function parse_article_appearances(wikitext: string, options?: Partial<ParseOptions> & { format?: "external" }): ArticleAppearances;
+export function parse_article_appearances(wikitext: string, options: Partial<ParseOptions> & { format: "tagged" }): Tagged<ArticleAppearances>;
+export function parse_infobox(wikitext: string, fields: FieldSpec[], rules?: TextRules): ParsedInfobox;
+export function parse_timeline(wikitext: string, rules?: TextRules): ParsedTimeline;
+export function query(wikitext: string, selector: string, format?: "external"): QueryOutput;
+export function query(wikitext: string, selector: string, format: "tagged"): Tagged<QueryOutput>;
+export function render_text(wikitext: string, rules?: TextRules): string;
+export function to_wikitext(nodes: SimpleNode[]): string;
+export function configure(config?: Partial<WikiConfig>): void;
+export function normalize_title(title: string): string;
@@ -0,0 +1,125 @@
+use std::fmt::Display;
+use std::panic::{self, AssertUnwindSafe};
+
+use neon::prelude::*;
+use rayon::prelude::*;
+use serde::Serialize;
+use wikitext_core::tagged::{with_node_format, NodeFormat};
+
+use crate::js;
+
+/// Outcome of parsing one item of a batch, shaped like the results of `Promise.allSettled`.
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum Settled<T> {
+    Fulfilled { value: T },
+    Rejected { reason: String },
+}
+
+/// Runs `f` on every item in parallel. An error or a panic only rejects the item it happened on.
+pub fn settle_all<T, E, F>(items: &[String], f: F) -> Vec<Settled<T>>
+where
+    T: Send,
+    E: Display,
+    F: Fn(&str) -> Result<T, E> + Sync,
+{
+    items
+        .par_iter()
+        .map(
+            |item| match panic::catch_unwind(AssertUnwindSafe(|| f(item))) {
+                Ok(Ok(value)) => Settled::Fulfilled { value },
+                Ok(Err(error)) => Settled::Rejected {
+                    reason: error.to_string(),
+                },
+                Err(payload) => Settled::Rejected {
+                    reason: panic_message(payload.as_ref()),
+                },
+            },
+        )
+        .collect()
+}
+
+/// Takes the array of wikitexts passed as the first argument and returns a promise
+/// that resolves with the settled results once all of them are processed on the rayon thread pool.
+/// Nodes in the results are serialized in `format`.
+pub fn spawn_batch<'a, T, E, F>(
+    cx: &mut FunctionContext<'a>,
+    format: NodeFormat,
+    f: F,
+) -> JsResult<'a, JsPromise>
+where
+    T: Serialize + Send + 'static,
+    E: Display,
+    F: Fn(&str) -> Result<T, E> + Send + Sync + 'static,
+{
+    let wikitexts = cx
+        .argument::<JsArray>(0)?
+        .to_vec(cx)?
+        .into_iter()
+        .map(|value| Ok(value.downcast_or_throw::<JsString, _>(cx)?.value(cx)))
+        .collect::<NeonResult<Vec<String>>>()?;
+    let channel = cx.channel();
+    let (deferred, promise) = cx.promise();
+
+    rayon::spawn(move || {
+        let results = settle_all(&wikitexts, f);
+        deferred.settle_with(&channel, move |mut cx| {
+            with_node_format(format, || js::to_value(&mut cx, &results))
+        });
+    });
+
+    Ok(promise)
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "Parser panicked".to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wikitext_core::appearances::{parse_appearances, Appearances};
+    use wikitext_core::options::ParseOptions;
+
+    #[test]
+    fn test_one_bad_item_does_not_reject_the_batch() {
+        let wikitexts = [
+            "{{App\n|c-characters=\n*[[Chian]]\n}}",
+            "Not an appearances template",
+            "{{App\n|c-characters=\n*[[Cordé\n}}",
+            "{{App\n|c-events=\n*[[Clone Wars]]\n}}",
+        ]
+        .map(String::from);
+        let results = settle_all(&wikitexts, |wikitext| {
+            parse_appearances(wikitext, &ParseOptions::default()).map(Appearances::into_owned)
+        });
+        assert!(
+            matches!(&results[0], Settled::Fulfilled { value } if value.links["c-characters"][0].name == "Chian")
+        );
+        assert!(
+            matches!(&results[1], Settled::Rejected { reason } if reason.contains("Template node was expected"))
+        );
+        assert!(
+            matches!(&results[2], Settled::Rejected { reason } if reason.contains("Invalid link syntax"))
+        );
+        assert!(matches!(&results[3], Settled::Fulfilled { .. }));
+    }
+
+    #[test]
+    fn test_panics_reject_their_item() {
+        let items = ["ok", "panic"].map(String::from);
+        let results = settle_all(&items, |item| match item {
+            "panic" => panic!("boom"),
+            _ => Ok::<_, String>(item.len()),
+        });
+        assert!(matches!(results[0], Settled::Fulfilled { value: 2 }));
+        assert!(matches!(&results[1], Settled::Rejected { reason } if reason == "boom"));
+    }
+}
@@ -0,0 +1,443 @@
+//! Serializes Rust values straight into JS values. Unlike `neon_serde3::to_value`, the property names
+//! of structs are created once per conversion instead of once per object, and arrays are created
+//! with their final length.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::{self, Display};
+
+use neon::prelude::*;
+use neon::result::Throw;
+use serde::ser::{self, Serialize};
+
+#[derive(Debug)]
+enum Error {
+    /// A JS exception is pending.
+    Throw(Throw),
+    Message(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Throw(_) => f.write_str("JS exception"),
+            Error::Message(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: Display>(message: T) -> Self {
+        Error::Message(message.to_string())
+    }
+}
+
+impl From<Throw> for Error {
+    fn from(throw: Throw) -> Self {
+        Error::Throw(throw)
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// Converts `value` to a JS value, throwing if it can't be represented.
+pub fn to_value<'j, C, T>(cx: &mut C, value: &T) -> JsResult<'j, JsValue>
+where
+    C: Context<'j>,
+    T: Serialize + ?Sized,
+{
+    let mut keys = HashMap::new();
+    let serializer = Serializer {
+        cx: &mut *cx,
+        keys: &mut keys,
+    };
+    match value.serialize(serializer) {
+        Ok(value) => Ok(value),
+        Err(Error::Throw(throw)) => Err(throw),
+        Err(Error::Message(message)) => cx.throw_error(message),
+    }
+}
+
+/// Property names of the structs converted so far.
+type Keys<'j> = HashMap<&'static str, Handle<'j, JsString>>;
+
+struct Serializer<'a, 'j, C> {
+    cx: &'a mut C,
+    keys: &'a mut Keys<'j>,
+}
+
+impl<'a, 'j, C: Context<'j>> Serializer<'a, 'j, C> {
+    fn reborrow(&mut self) -> Serializer<'_, 'j, C> {
+        Serializer {
+            cx: &mut *self.cx,
+            keys: &mut *self.keys,
+        }
+    }
+
+    fn key(&mut self, name: &'static str) -> Handle<'j, JsString> {
+        let cx = &mut *self.cx;
+        *self.keys.entry(name).or_insert_with(|| cx.string(name))
+    }
+
+    fn value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<Handle<'j, JsValue>> {
+        value.serialize(self.reborrow())
+    }
+
+    fn number(self, value: f64) -> Result<Handle<'j, JsValue>> {
+        Ok(self.cx.number(value).upcast())
+    }
+
+    /// `{ [variant]: value }`, the externally tagged form of enum variants.
+    fn wrap(
+        &mut self,
+        variant: &'static str,
+        value: Handle<'j, JsValue>,
+    ) -> Result<Handle<'j, JsValue>> {
+        let object = self.cx.empty_object();
+        let key = self.key(variant);
+        object.set(self.cx, key, value)?;
+        Ok(object.upcast())
+    }
+}
+
+impl<'a, 'j, C: Context<'j>> ser::Serializer for Serializer<'a, 'j, C> {
+    type Ok = Handle<'j, JsValue>;
+    type Error = Error;
+
+    type SerializeSeq = Elements<'a, 'j, C>;
+    type SerializeTuple = Elements<'a, 'j, C>;
+    type SerializeTupleStruct = Elements<'a, 'j, C>;
+    type SerializeTupleVariant = Variant<Elements<'a, 'j, C>>;
+    type SerializeMap = Properties<'a, 'j, C>;
+    type SerializeStruct = Properties<'a, 'j, C>;
+    type SerializeStructVariant = Variant<Properties<'a, 'j, C>>;
+
+    fn serialize_bool(self, value: bool) -> Result<Self::Ok> {
+        Ok(self.cx.boolean(value).upcast())
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Self::Ok> {
+        self.number(value.into())
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Self::Ok> {
+        self.number(value.into())
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Self::Ok> {
+        self.number(value.into())
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Self::Ok> {
+        self.number(value as f64)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Self::Ok> {
+        self.number(value.into())
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Self::Ok> {
+        self.number(value.into())
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Self::Ok> {
+        self.number(value.into())
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Self::Ok> {
+        self.number(value as f64)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<Self::Ok> {
+        self.number(value.into())
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<Self::Ok> {
+        self.number(value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<Self::Ok> {
+        self.serialize_str(value.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Self::Ok> {
+        match JsString::try_new(self.cx, value) {
+            Ok(string) => Ok(string.upcast()),
+            Err(_) => Err(Error::Message(format!(
+                "String of {} bytes is too long for JS",
+                value.len()
+            ))),
+        }
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok> {
+        let mut array = Elements::new(self, value.len())?;
+        for byte in value {
+            ser::SerializeSeq::serialize_element(&mut array, byte)?;
+        }
+        ser::SerializeSeq::end(array)
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(self.cx.null().upcast())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(self.cx.null().upcast())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        mut self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        Ok(self.key(variant).upcast())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        mut self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        let value = self.value(value)?;
+        self.wrap(variant, value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Elements::new(self, len.unwrap_or(0))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        Elements::new(self, len)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Elements::new(self, len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(Variant {
+            variant,
+            inner: Elements::new(self, len)?,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(Properties::new(self))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(Properties::new(self))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(Variant {
+            variant,
+            inner: Properties::new(self),
+        })
+    }
+}
+
+struct Elements<'a, 'j, C> {
+    serializer: Serializer<'a, 'j, C>,
+    array: Handle<'j, JsArray>,
+    len: u32,
+}
+
+impl<'a, 'j, C: Context<'j>> Elements<'a, 'j, C> {
+    fn new(serializer: Serializer<'a, 'j, C>, len: usize) -> Result<Self> {
+        let len = u32::try_from(len)
+            .map_err(|_| Error::Message(format!("Array of {len} items is too long for JS")))?;
+        Ok(Elements {
+            array: JsArray::new(serializer.cx, len),
+            serializer,
+            len: 0,
+        })
+    }
+}
+
+impl<'a, 'j, C: Context<'j>> ser::SerializeSeq for Elements<'a, 'j, C> {
+    type Ok = Handle<'j, JsValue>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let value = self.serializer.value(value)?;
+        self.array.set(self.serializer.cx, self.len, value)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.array.upcast())
+    }
+}
+
+impl<'a, 'j, C: Context<'j>> ser::SerializeTuple for Elements<'a, 'j, C> {
+    type Ok = Handle<'j, JsValue>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'a, 'j, C: Context<'j>> ser::SerializeTupleStruct for Elements<'a, 'j, C> {
+    type Ok = Handle<'j, JsValue>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct Properties<'a, 'j, C> {
+    serializer: Serializer<'a, 'j, C>,
+    object: Handle<'j, JsObject>,
+    /// Key of the map entry whose value comes next.
+    key: Option<Handle<'j, JsValue>>,
+}
+
+impl<'a, 'j, C: Context<'j>> Properties<'a, 'j, C> {
+    fn new(serializer: Serializer<'a, 'j, C>) -> Self {
+        Properties {
+            object: serializer.cx.empty_object(),
+            serializer,
+            key: None,
+        }
+    }
+}
+
+impl<'a, 'j, C: Context<'j>> ser::SerializeMap for Properties<'a, 'j, C> {
+    type Ok = Handle<'j, JsValue>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.key = Some(self.serializer.value(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::Message("Map value without a key".to_string()))?;
+        let value = self.serializer.value(value)?;
+        self.object.set(self.serializer.cx, key, value)?;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.object.upcast())
+    }
+}
+
+impl<'a, 'j, C: Context<'j>> ser::SerializeStruct for Properties<'a, 'j, C> {
+    type Ok = Handle<'j, JsValue>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let value = self.serializer.value(value)?;
+        let key = self.serializer.key(name);
+        self.object.set(self.serializer.cx, key, value)?;
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.object.upcast())
+    }
+}
+
+/// Tuple or struct variant, wrapped in `{ [variant]: ... }` once complete.
+struct Variant<T> {
+    variant: &'static str,
+    inner: T,
+}
+
+impl<'a, 'j, C: Context<'j>> ser::SerializeTupleVariant for Variant<Elements<'a, 'j, C>> {
+    type Ok = Handle<'j, JsValue>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        let Elements {
+            mut serializer,
+            array,
+            ..
+        } = self.inner;
+        serializer.wrap(self.variant, array.upcast())
+    }
+}
+
+impl<'a, 'j, C: Context<'j>> ser::SerializeStructVariant for Variant<Properties<'a, 'j, C>> {
+    type Ok = Handle<'j, JsValue>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, name, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        let Properties {
+            mut serializer,
+            object,
+            ..
+        } = self.inner;
+        serializer.wrap(self.variant, object.upcast())
+    }
+}
@@ -1,276 +1,261 @@
-use std::collections::HashMap;
-use std::cmp;
-
 use neon::prelude::*;
-use parse_wiki_text::{Configuration, ListItem, Node};
 use serde::Serialize;
+use wikitext_core::appearances::{self, Appearances};
+use wikitext_core::configuration::WikiConfig;
+use wikitext_core::constructs::Constructs;
+use wikitext_core::diagnostics::Diagnostic;
+use wikitext_core::infobox::{extract_infobox, find_infobox, FieldSpec, Infobox};
+use wikitext_core::options::ParseOptions;
+use wikitext_core::query::{self, QueryMatch, Selector};
+use wikitext_core::serialize::to_wikitext;
+use wikitext_core::tagged::{with_node_format, NodeFormat};
+use wikitext_core::text::{render_text, TextRules};
+use wikitext_core::title;
+use wikitext_core::{configuration, parse_checked, parse_document, timeline};
+use wikitext_core::{ParsedWikitext, SimpleNode};
+
+use batch::spawn_batch;
+
+mod batch;
+mod js;
+#[cfg(test)]
+mod typescript;
 
-#[derive(Serialize, Debug, PartialEq, Clone)]
-struct SimpleTemplate {
-    name: String,
-    parameters: Vec<SimpleParameter>,
+/// Node format is an optional argument at `index`, `"external"` by default or `"tagged"`.
+fn node_format_arg(cx: &mut FunctionContext, index: i32) -> NeonResult<NodeFormat> {
+    match cx.argument_opt(index) {
+        Some(arg) if !arg.is_a::<JsUndefined, _>(cx) => {
+            neon_serde3::from_value(cx, arg).or_else(|e| cx.throw_error(e.to_string()))
+        }
+        _ => Ok(NodeFormat::default()),
+    }
 }
 
-#[derive(Serialize, Debug, PartialEq, Clone)]
-enum SimpleNode {
-    List(Vec<Vec<SimpleNode>>),
-    Template(SimpleTemplate),
-    Link {
-        target: String,
-        text: String,
-    },
-    Text(String),
+/// Every parsing function takes an optional `ParseOptions` object after its wikitext.
+fn options_arg(cx: &mut FunctionContext) -> NeonResult<ParseOptions> {
+    match cx.argument_opt(1) {
+        Some(arg) if !arg.is_a::<JsUndefined, _>(cx) => {
+            neon_serde3::from_value(cx, arg).or_else(|e| cx.throw_error(e.to_string()))
+        }
+        _ => Ok(ParseOptions::default()),
+    }
 }
 
-#[derive(Serialize, Debug, PartialEq, Clone)]
-struct SimpleParameter {
-    name: Option<String>,
-    value: Vec<SimpleNode>,
+/// Returns `{ nodes, diagnostics }`, where diagnostics are always empty in strict mode.
+fn parse(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let wikitext = cx.argument::<JsString>(0)?.value(&mut cx);
+    let options = options_arg(&mut cx)?;
+    let parsed = parse_checked(&wikitext, &options).or_else(|e| cx.throw_error(e.to_string()))?;
+
+    with_node_format(options.format, || js::to_value(&mut cx, &parsed))
 }
 
-#[derive(Serialize, Debug)]
-struct Appearances {
-    nodes: Vec<SimpleNode>,
-    links: HashMap<String, Vec<Appearance>>, // TODO: Should value be a set instead of vec?
+/// `parse` with owned nodes converted by `neon_serde3`, as before `js::to_value`. The baseline of
+/// `scripts/bench-native.js`, and what the tests check `js::to_value` against.
+fn parse_baseline(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let wikitext = cx.argument::<JsString>(0)?.value(&mut cx);
+    let options = options_arg(&mut cx)?;
+    let parsed = parse_checked(&wikitext, &options)
+        .or_else(|e| cx.throw_error(e.to_string()))?
+        .into_owned();
+
+    with_node_format(options.format, || neon_serde3::to_value(&mut cx, &parsed))
+        .or_else(|e| cx.throw_error(e.to_string()))
 }
 
-#[derive(Serialize, Debug)]
-struct Appearance {
-    name: String,
-    templates: Option<Vec<SimpleTemplate>>,
+/// Takes the wikitext of a single appearances template, one of `templateNames` if given.
+fn parse_appearances(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let wikitext = cx.argument::<JsString>(0)?.value(&mut cx);
+    let options = options_arg(&mut cx)?;
+    let ret = appearances::parse_appearances(&wikitext, &options)
+        .or_else(|e| cx.throw_error(e.to_string()))?;
+
+    with_node_format(options.format, || js::to_value(&mut cx, &ret))
 }
 
-fn reduce_nodes_to_text(nodes: &Vec<Node>) -> String {
-    nodes
-        .iter()
-        .map(|node| match node {
-            Node::Text { value, .. } => value,
-            _ => "",
-        })
-        .collect()
+/// Like `parse`, but takes an array of wikitexts and parses them off the main thread.
+fn parse_many(mut cx: FunctionContext) -> JsResult<JsPromise> {
+    let options = options_arg(&mut cx)?;
+    spawn_batch(&mut cx, options.format, move |wikitext| {
+        parse_checked(wikitext, &options).map(ParsedWikitext::into_owned)
+    })
 }
 
-fn parse_list_items(items: &Vec<ListItem>, wikitext: &str) -> SimpleNode {
-    let mut item_list = Vec::new();
-    for item in items {
-        item_list.push(parse_nodes(&item.nodes, wikitext));
-    }
-    SimpleNode::List(item_list)
+/// Like `parse_appearances`, but takes an array of wikitexts and parses them off the main thread.
+fn parse_appearances_many(mut cx: FunctionContext) -> JsResult<JsPromise> {
+    let options = options_arg(&mut cx)?;
+    spawn_batch(&mut cx, options.format, move |wikitext| {
+        appearances::parse_appearances(wikitext, &options).map(Appearances::into_owned)
+    })
 }
 
-fn parse_nodes(nodes: &Vec<Node>, wikitext: &str) -> Vec<SimpleNode> {
-    let mut node_list = Vec::new();
-    for node in nodes {
-        match node {
-            Node::Link { target, text, .. } => {
-                node_list.push(SimpleNode::Link {
-                    target: target.to_string(),
-                    text: reduce_nodes_to_text(text),
-                });
-            }
-            Node::UnorderedList { items, .. } => {
-                node_list.push(parse_list_items(&items, wikitext));
-            }
-            Node::Text { value, .. } => {
-                node_list.push(SimpleNode::Text(value.to_string()));
-            }
-            Node::CharacterEntity { character, .. } => {
-                node_list.push(SimpleNode::Text(character.to_string()));
-            }
-            Node::Template {
-                name, parameters, ..
-            } => {
-                node_list.push(SimpleNode::Template(SimpleTemplate {
-                    name: reduce_nodes_to_text(&name),
-                    parameters: parameters
-                        .iter()
-                        .map(|param| {
-                            // Lists don't get parsed inside templates, so parse the raw wikitext
-                            // Start after param name, which is part of the param wikitext
-                            let name = param.name.as_ref().map(|name| reduce_nodes_to_text(name));
-                            let start = match &name {
-                                Some(name) => param.start + name.len() + 1,
-                                None => param.start,
-                            };
-                            let param_wt = &wikitext[start..param.end];
-                            SimpleParameter {
-                                name,
-                                value: parse_nodes(
-                                    &Configuration::default().parse(param_wt).nodes,
-                                    param_wt,
-                                ),
-                            }
-                        })
-                        .collect(),
-                    }));
-            }
-            _ => (),
-        };
-    }
-    node_list
+/// Appearances of every appearances template in a whole article, `{{App}}` unless
+/// `templateNames` are given.
+fn parse_article_appearances(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let wikitext = cx.argument::<JsString>(0)?.value(&mut cx);
+    let options = options_arg(&mut cx)?;
+    let ret = appearances::parse_article_appearances(&wikitext, &options)
+        .or_else(|e| cx.throw_error(e.to_string()))?;
+
+    with_node_format(options.format, || js::to_value(&mut cx, &ret))
 }
 
-fn collect_links_from_nodes(nodes: &Vec<SimpleNode>) -> Vec<Appearance> {
-    let mut appearances = Vec::new();
-    for node in nodes {
-        match node {
-            SimpleNode::Link { target, .. } => appearances.push(Appearance {
-                name: target.to_string(),
-                templates: None,
-            }),
-            SimpleNode::List(items) => {
-                for item in items {
-                    appearances.append(&mut collect_links_from_nodes(item));
-                }
-            }
-            SimpleNode::Template(template) => match appearances.last_mut() {
-                Some(mut appearance) => {
-                    if let None = appearance.templates {
-                        appearance.templates = Some(Vec::new());
-                    };
-                    appearance
-                        .templates
-                        .as_mut()
-                        .unwrap()
-                        .push(template.clone());
-                }
-                None => {} // This happens for non-link appearances that have a template
-            },
-            _ => (),
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+struct ParsedInfobox<'a> {
+    infobox: Option<Infobox<'a>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Template expansion rules are an optional argument at `index`, merged over the defaults.
+fn text_rules_arg(cx: &mut FunctionContext, index: i32) -> NeonResult<TextRules> {
+    match cx.argument_opt(index) {
+        Some(arg) if !arg.is_a::<JsUndefined, _>(cx) => {
+            let overrides =
+                neon_serde3::from_value(cx, arg).or_else(|e| cx.throw_error(e.to_string()))?;
+            Ok(TextRules::with_overrides(overrides))
         }
+        _ => Ok(TextRules::default()),
     }
-    appearances
 }
 
-fn parse_wikitext(cx: &mut FunctionContext) -> NeonResult<Vec<SimpleNode>> {
-    let wikitext = cx.argument::<JsString>(0)?.value(cx);
-    let result = Configuration::default().parse(&wikitext);
-    if !result.warnings.is_empty() {
-        let erroneous_wt = result
-            .warnings
-            .iter()
-            .map(|warn| &wikitext[warn.start..warn.end])
-            .collect::<String>();
-        let erroneous_wt_big = result
-            .warnings
-            .iter()
-            .map(|warn| &wikitext[cmp::max(0, warn.start-30)..cmp::min(wikitext.len(), warn.end+30)])
-            .collect::<String>();
-        return cx.throw_error(format!(
-            "Parsing warnings: {:?}\nErroneous wikitext: {:?}\nContext: {:?}",
-            result.warnings, erroneous_wt, erroneous_wt_big
-        ));
-    }
+/// Takes an article, the field table, see `FieldSpec`, and optional `TextRules`. Parsing is always lenient,
+/// since a problem elsewhere in the article shouldn't prevent reading its infobox.
+fn parse_infobox(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let wikitext = cx.argument::<JsString>(0)?.value(&mut cx);
+    let fields = cx.argument::<JsValue>(1)?;
+    let fields: Vec<FieldSpec> =
+        neon_serde3::from_value(&mut cx, fields).or_else(|e| cx.throw_error(e.to_string()))?;
+    let rules = text_rules_arg(&mut cx, 2)?;
 
-    Ok(parse_nodes(&result.nodes, &wikitext))
-}
+    let parsed = parse_document(&wikitext, &Constructs::all());
+    let ret = ParsedInfobox {
+        infobox: find_infobox(&parsed.nodes)
+            .map(|template| extract_infobox(template, &fields, &rules)),
+        diagnostics: parsed.diagnostics,
+    };
 
-fn parse(mut cx: FunctionContext) -> JsResult<JsValue> {
-    let parsed = parse_wikitext(&mut cx)?;
+    js::to_value(&mut cx, &ret)
+}
 
-    Ok(neon_serde3::to_value(&mut cx, &parsed)
-        .or_else(|e| cx.throw_error(e.to_string()))
-        .unwrap())
+#[derive(Serialize, Debug)]
+#[cfg_attr(test, derive(ts_rs::TS))]
+struct QueryOutput<'a> {
+    matches: Vec<QueryMatch<'a>>,
+    diagnostics: &'a [Diagnostic],
 }
 
-fn parse_appearances(mut cx: FunctionContext) -> JsResult<JsValue> {
-    let parsed = parse_wikitext(&mut cx)?;
+/// Takes a wikitext, a selector such as `{{App|c-characters}} [[*]]` and the optional `NodeFormat`.
+/// Parsing is lenient, diagnostics are returned next to the matching nodes.
+fn query(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let wikitext = cx.argument::<JsString>(0)?.value(&mut cx);
+    let selector = cx.argument::<JsString>(1)?.value(&mut cx);
+    let selector: Selector = selector
+        .parse()
+        .or_else(|e: wikitext_core::Error| cx.throw_error(e.to_string()))?;
+    let format = node_format_arg(&mut cx, 2)?;
 
-    let mut ret = Appearances {
-        nodes: parsed,
-        links: HashMap::new(),
+    let parsed = parse_document(&wikitext, &Constructs::all());
+    let ret = QueryOutput {
+        matches: query::query(&parsed.nodes, &selector),
+        diagnostics: &parsed.diagnostics,
     };
-    if let SimpleNode::Template(template) = &ret.nodes[0] {
-        for param in &template.parameters {
-            if let Some(name) = &param.name {
-                ret.links
-                    .insert(name.to_string(), collect_links_from_nodes(&param.value));
-            } else {
-                eprintln!("{:#?}", param);
-                return cx.throw_error("Incorrect input. Template parameter name was expected. (App had an unnamed parameter)");
-            }
+
+    with_node_format(format, || js::to_value(&mut cx, &ret))
+}
+
+/// Overrides the parser configuration for all subsequent calls. Takes an object with
+/// `WikiConfig` fields, missing ones keep their Wookieepedia value.
+fn configure(mut cx: FunctionContext) -> JsResult<JsUndefined> {
+    let config = match cx.argument_opt(0) {
+        Some(arg) if !arg.is_a::<JsUndefined, _>(&mut cx) => {
+            neon_serde3::from_value(&mut cx, arg).or_else(|e| cx.throw_error(e.to_string()))?
         }
-    } else {
-        return cx.throw_error("Incorrect input. Template node was expected.");
-    }
+        _ => WikiConfig::default(),
+    };
+    configuration::set(&config);
+    Ok(cx.undefined())
+}
 
-    Ok(neon_serde3::to_value(&mut cx, &ret)
-        .or_else(|e| cx.throw_error(e.to_string()))
-        .unwrap())
+/// Takes the wikitext of a timeline page and optional `TextRules` for the titles.
+/// Parsing is lenient, diagnostics are returned next to the rows.
+fn parse_timeline(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let wikitext = cx.argument::<JsString>(0)?.value(&mut cx);
+    let rules = text_rules_arg(&mut cx, 1)?;
+    let ret =
+        timeline::parse_timeline(&wikitext, &rules).or_else(|e| cx.throw_error(e.to_string()))?;
+
+    js::to_value(&mut cx, &ret)
 }
 
-#[neon::main]
-fn main(mut cx: ModuleContext) -> NeonResult<()> {
-    cx.export_function("parse", parse)?;
-    cx.export_function("parse_appearances", parse_appearances)?;
-    Ok(())
+/// Readable plain text of a wikitext, with templates expanded by the optional `TextRules`.
+fn render_plain_text(mut cx: FunctionContext) -> JsResult<JsString> {
+    let wikitext = cx.argument::<JsString>(0)?.value(&mut cx);
+    let rules = text_rules_arg(&mut cx, 1)?;
+    let parsed = parse_document(&wikitext, &Constructs::all());
+    Ok(cx.string(render_text(&parsed.nodes, &rules).trim()))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_parse_list_items() {
-        let wt = "*[[target]]";
-        let doc = Configuration::default().parse(wt);
-        assert!(matches!(&doc.nodes[0], Node::UnorderedList { .. }));
-        if let Node::UnorderedList { items, .. } = &doc.nodes[0] {
-            let parsed = parse_list_items(items, wt);
-            assert_eq!(
-                parsed,
-                SimpleNode::List(vec![vec![SimpleNode::Link {
-                    target: "target".to_string(),
-                    text: "target".to_string(),
-                }]])
-            );
-        }
-    }
+/// Takes nodes as returned by `parse` and writes them back out as canonical wikitext.
+fn serialize(mut cx: FunctionContext) -> JsResult<JsString> {
+    let nodes = cx.argument::<JsValue>(0)?;
+    let nodes: Vec<SimpleNode> =
+        neon_serde3::from_value(&mut cx, nodes).or_else(|e| cx.throw_error(e.to_string()))?;
+    Ok(cx.string(to_wikitext(&nodes)))
+}
 
-    #[test]
-    fn test_list_in_template() {
-        let wt = "{{template_name|param_name=\n*[[link_target]]}}";
-        let doc = Configuration::default().parse(wt);
-        assert!(matches!(&doc.nodes[0], Node::Template { .. }));
-        let parsed = parse_nodes(&doc.nodes, wt);
-        assert_eq!(
-            parsed,
-            vec![SimpleNode::Template( SimpleTemplate {
-                name: "template_name".to_string(),
-                parameters: vec![SimpleParameter {
-                    name: Some("param_name".to_string()),
-                    value: vec![SimpleNode::List(vec![vec![SimpleNode::Link {
-                        target: "link_target".to_string(),
-                        text: "link_target".to_string(),
-                    }]]),],
-                }],
-            })]
-        );
-    }
+fn normalize_title(mut cx: FunctionContext) -> JsResult<JsString> {
+    let raw = cx.argument::<JsString>(0)?.value(&mut cx);
+    Ok(cx.string(title::normalize_title(&raw)))
+}
+
+/// Declares the exported functions with their TypeScript signatures, which `typescript.rs` writes
+/// to `index.d.cts`, so the definitions can't miss an export. Overloads get one signature each.
+macro_rules! exports {
+    ($($name:literal => $function:ident $(: $signature:literal)+,)*) => {
+        #[cfg(test)]
+        const EXPORTS: &[(&str, &[&str])] = &[$(($name, &[$($signature),+])),*];
 
-    #[test]
-    fn test_1stid() {
-        let wt = "{{App
-|c-characters=
-*[[1138 (Geonosis)|1138]] {{1st}}
-*[[Chian]] {{1st}}
-*[[COO-2180]] {{1st}}
-*[[Cordé]] {{1st}}
-*[[CT-411]] \"Ponds\" {{1st}}
-*[[Theomet Danlé]] {{1st}}
-*[[Braata Danlos]] {{1st}} {{C|Statue only}}
-*[[Oakie Dokes]] {{1st}}
-*[[Lexi Dio]] {{1st}}
-*[[Tox Don]] {{1stID|Tox Don}}
-|c-events=
-*[[Clone Wars]] {{1st}}
-**[[Battle of Geonosis]] {{1st}} {{C|[[link]]}}
-}}";
-        let parsed = parse_nodes(&Configuration::default().parse(wt).nodes, wt);
-        if let SimpleNode::Template(SimpleTemplate { parameters, .. }) = &parsed[0] {
-            for param in parameters {
-                dbg!(&param.name, collect_links_from_nodes(&param.value));
-            }
+        #[neon::main]
+        fn main(mut cx: ModuleContext) -> NeonResult<()> {
+            $(cx.export_function($name, $function)?;)*
+            Ok(())
         }
-    }
+    };
+}
+
+exports! {
+    "parse" => parse
+        : r#"(wikitext: string, options?: Partial<ParseOptions> & { format?: "external" }): ParsedWikitext"#
+        : r#"(wikitext: string, options: Partial<ParseOptions> & { format: "tagged" }): Tagged<ParsedWikitext>"#,
+    "parse_baseline" => parse_baseline
+        : r#"(wikitext: string, options?: Partial<ParseOptions> & { format?: "external" }): ParsedWikitext"#
+        : r#"(wikitext: string, options: Partial<ParseOptions> & { format: "tagged" }): Tagged<ParsedWikitext>"#,
+    "parse_appearances" => parse_appearances
+        : r#"(wikitext: string, options?: Partial<ParseOptions> & { format?: "external" }): Appearances"#
+        : r#"(wikitext: string, options: Partial<ParseOptions> & { format: "tagged" }): Tagged<Appearances>"#,
+    "parse_many" => parse_many
+        : r#"(wikitexts: string[], options?: Partial<ParseOptions> & { format?: "external" }): Promise<Settled<ParsedWikitext>[]>"#
+        : r#"(wikitexts: string[], options: Partial<ParseOptions> & { format: "tagged" }): Promise<Settled<Tagged<ParsedWikitext>>[]>"#,
+    "parse_appearances_many" => parse_appearances_many
+        : r#"(wikitexts: string[], options?: Partial<ParseOptions> & { format?: "external" }): Promise<Settled<Appearances>[]>"#
+        : r#"(wikitexts: string[], options: Partial<ParseOptions> & { format: "tagged" }): Promise<Settled<Tagged<Appearances>>[]>"#,
+    "parse_article_appearances" => parse_article_appearances
+        : r#"(wikitext: string, options?: Partial<ParseOptions> & { format?: "external" }): ArticleAppearances"#
+        : r#"(wikitext: string, options: Partial<ParseOptions> & { format: "tagged" }): Tagged<ArticleAppearances>"#,
+    "parse_infobox" => parse_infobox
+        : "(wikitext: string, fields: FieldSpec[], rules?: TextRules): ParsedInfobox",
+    "parse_timeline" => parse_timeline
+        : "(wikitext: string, rules?: TextRules): ParsedTimeline",
+    "query" => query
+        : r#"(wikitext: string, selector: string, format?: "external"): QueryOutput"#
+        : r#"(wikitext: string, selector: string, format: "tagged"): Tagged<QueryOutput>"#,
+    "render_text" => render_plain_text
+        : "(wikitext: string, rules?: TextRules): string",
+    "to_wikitext" => serialize
+        : "(nodes: SimpleNode[]): string",
+    "configure" => configure
+        : "(config?: Partial<WikiConfig>): void",
+    "normalize_title" => normalize_title
+        : "(title: string): string",
 }
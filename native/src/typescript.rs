@@ -0,0 +1,112 @@
+//! TypeScript definitions of the module, generated from the Rust types and committed as `index.d.cts`,
+//! so a change to a serialized type shows up in the TypeScript pipeline. Run `UPDATE_TYPES=1 cargo test`
+//! to regenerate them.
+
+use std::{env, fs};
+
+use ts_rs::TS;
+use wikitext_core::appearances::{
+    Appearance, AppearanceKind, AppearanceTree, Appearances, ArticleAppearances,
+    SectionAppearances, TemplateAppearances,
+};
+use wikitext_core::categories::{CategoryAliases, ContinuityAppearances};
+use wikitext_core::configuration::WikiConfig;
+use wikitext_core::constructs::Constructs;
+use wikitext_core::diagnostics::{Diagnostic, DiagnosticKind};
+use wikitext_core::infobox::{FieldSpec, Infobox, InfoboxValue};
+use wikitext_core::options::ParseOptions;
+use wikitext_core::qualifiers::Qualifier;
+use wikitext_core::query::QueryMatch;
+use wikitext_core::span::Span;
+use wikitext_core::tagged::{NodeFormat, TaggedNode};
+use wikitext_core::text::{ParamKey, ParamRef, TemplateRule, TextRules};
+use wikitext_core::timeline::{ParsedTimeline, TimelineRow};
+use wikitext_core::{
+    ParsedWikitext, SimpleDefinitionItem, SimpleNode, SimpleParameter, SimpleTable,
+    SimpleTableCell, SimpleTableRow, SimpleTemplate,
+};
+
+use crate::batch::Settled;
+use crate::{ParsedInfobox, QueryOutput, EXPORTS};
+
+const DEFINITIONS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/index.d.cts");
+
+const HEADER: &str =
+    "// Generated from the Rust types by `UPDATE_TYPES=1 cargo test` in native/, do not edit.\n\n";
+
+/// `T` with its `SimpleNode`s in the `NodeFormat::Tagged` format.
+const TAGGED: &str = "\
+export type Tagged<T> = T extends SimpleNode ? TaggedNode : T extends Array<infer U> ? Array<Tagged<U>> : T extends object ? { [K in keyof T]: Tagged<T[K]> } : T;
+
+";
+
+fn definitions() -> String {
+    let declarations = [
+        Span::decl(),
+        SimpleNode::decl(),
+        SimpleTemplate::decl(),
+        SimpleParameter::decl(),
+        SimpleDefinitionItem::decl(),
+        SimpleTable::decl(),
+        SimpleTableRow::decl(),
+        SimpleTableCell::decl(),
+        DiagnosticKind::decl(),
+        Diagnostic::decl(),
+        ParsedWikitext::decl(),
+        Constructs::decl(),
+        ParseOptions::decl(),
+        Settled::<()>::decl(),
+        AppearanceKind::decl(),
+        Qualifier::decl(),
+        Appearance::decl(),
+        AppearanceTree::decl(),
+        ContinuityAppearances::decl(),
+        CategoryAliases::decl(),
+        Appearances::decl(),
+        TemplateAppearances::decl(),
+        SectionAppearances::decl(),
+        ArticleAppearances::decl(),
+        FieldSpec::decl(),
+        InfoboxValue::decl(),
+        Infobox::decl(),
+        ParsedInfobox::decl(),
+        ParamKey::decl(),
+        ParamRef::decl(),
+        TemplateRule::decl(),
+        TextRules::decl(),
+        TimelineRow::decl(),
+        ParsedTimeline::decl(),
+        QueryMatch::decl(),
+        QueryOutput::decl(),
+        WikiConfig::decl(),
+        NodeFormat::decl(),
+        TaggedNode::decl(),
+    ];
+    let mut definitions = HEADER.to_string();
+    for declaration in &declarations {
+        definitions.push_str("export ");
+        definitions.push_str(declaration);
+        definitions.push_str("\n\n");
+    }
+    definitions.push_str(TAGGED);
+    for (name, signatures) in EXPORTS {
+        for signature in *signatures {
+            definitions.push_str(&format!("export function {name}{signature};\n"));
+        }
+    }
+    definitions
+}
+
+#[test]
+fn test_definitions_are_up_to_date() {
+    let generated = definitions();
+    if env::var_os("UPDATE_TYPES").is_some() {
+        fs::write(DEFINITIONS, &generated).unwrap();
+        return;
+    }
+    let committed = fs::read_to_string(DEFINITIONS).unwrap_or_default();
+    assert!(
+        committed == generated,
+        "index.d.cts is out of date with the Rust types, run `UPDATE_TYPES=1 cargo test` in native/"
+    );
+}
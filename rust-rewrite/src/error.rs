@@ -3,6 +3,13 @@ pub enum Error {
     TimelineParsing(String),
     Qwe(String),
     UnexpectedWikitextStructure(String),
+    Parsing(wikitext_core::Error),
+}
+
+impl From<wikitext_core::Error> for Error {
+    fn from(error: wikitext_core::Error) -> Self {
+        Error::Parsing(error)
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
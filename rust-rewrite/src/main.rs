@@ -1,6 +1,6 @@
-#![allow(dead_code, unused_imports)]
+#![allow(dead_code, unused_imports, unused_variables)]
 use core::time;
-use std::{cmp::min, collections::HashMap, env, fmt::Write, fs, sync::LazyLock, thread, time::Duration};
+use std::{cmp::min, collections::HashMap, env, fmt::Write, fs, path::PathBuf, sync::LazyLock, thread, time::Duration};
 
 use clap::{Parser, ValueEnum};
 use error::{Error, Result};
@@ -9,8 +9,10 @@ use indicatif::{MultiProgress, ProgressBar, ProgressState, ProgressStyle};
 use indicatif_log_bridge::LogWrapper;
 use log::{error, info, warn};
 use mongodb::{bson::Document, Client};
-use parse_wiki_text::{Configuration, Node, TableRow};
 use serde::{Deserialize, Serialize};
+use wikitext_core::configuration::{self, WikiConfig};
+use wikitext_core::text::TextRules;
+use wikitext_core::timeline::parse_timeline;
 
 mod error;
 mod model;
@@ -25,10 +27,14 @@ enum Timeline {
 #[command(version, about, long_about=None   )]
 struct Args {
     #[arg(value_enum)]
-    timeline: Timeline,
+    timeline: Option<Timeline>,
 
     #[arg(short, long)]
     cache: bool,
+
+    /// JSON file overriding fields of the Wookieepedia parser configuration
+    #[arg(long)]
+    wiki_config: Option<PathBuf>,
 }
 
 fn log_test() {
@@ -96,44 +102,6 @@ fn log_test3() {
     bar.finish();
 }
 
-macro_rules! ensure {
-    ($condition:expr, $error_value:expr) => {
-        if !($condition) {
-            return Err($error_value);
-        }
-    };
-}
-
-fn validate_timeline_header(header: &TableRow) -> Result<()> {
-    let expected_columns = ["Year", "", "Title", "Released"];
-
-    ensure!(
-        header.cells[1].content.is_empty(),
-        Error::TimelineParsing("column 1 must be empty".to_string())
-    );
-    for (i, expected_column) in expected_columns.iter().enumerate() {
-        ensure!(
-            header.cells.len() == 4,
-            Error::TimelineParsing(format!("expected 4 header cells, found {}", header.cells.len()))
-        );
-        if i == 1 {
-            continue;
-        }
-        if let Node::Text { value, .. } = header.cells[i].content[0] {
-            ensure!(
-                value == *expected_column,
-                Error::TimelineParsing(format!("column {} must be '{}'", i, expected_column))
-            );
-        } else {
-            return Err(Error::TimelineParsing(format!(
-                "column {} was expected, but non text node was found",
-                expected_column
-            )));
-        }
-    }
-    Ok(())
-}
-
 #[tokio::main]
 async fn main() -> Result<()> {
     // let wt = fs::read_to_string("../debug/force-storm.wiki").expect("");
@@ -167,7 +135,7 @@ async fn main() -> Result<()> {
     //     }
     // }
 
-    // let args = Args::parse();
+    let args = Args::parse();
 
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
         .format_timestamp(None)
@@ -207,110 +175,26 @@ async fn main() -> Result<()> {
 
     // Parse timeline
     let wt = fs::read_to_string("../debug/timeline").expect("");
-    let timeline_nodes = Configuration::default().parse(&wt).nodes;
-
-    let tables = find_tables(timeline_nodes);
-    info!("{} tables found", tables.len());
-
-    let table = &tables[1];
-
-    if let Node::Table { rows, .. } = table {
-        info!("{} rows in the table", rows.len());
-        let header = &rows[0];
-        validate_timeline_header(header)?;
-
-        for row in &rows[1..10] {
-            if row.cells.len() != 4 {
-                return Err(Error::TimelineParsing(
-                    "timeline table rows should have exactly 4 cells".to_string(),
-                ));
-            }
-
-            let year = reduce_nodes_to_text(&row.cells[0].content);
-            let title = parse_title(&row.cells[2].content);
-            // let title = decode_html_entities(&title_raw);
-            println!("{title:#?}");
-            let release_date = reduce_nodes_to_text(&row.cells[3].content);
-
-            // println!("{year} {title} {release_date}");
-
-            let media_type_cell = &row.cells[1].content;
-            if media_type_cell.len() != 1 || !matches!(media_type_cell[0], Node::Text { .. }) {
-                panic!("media type cell should contain a signle text node");
-            }
-            if let Node::Text { value, .. } = media_type_cell[0] {
-                println!("{value}");
-            } else {
-                unreachable!();
-            }
-        }
-    } else {
-        unreachable!("find_tables should only return Node::Table variants");
-    }
-
-    Ok(())
-}
-
-fn find_tables(nodes: Vec<Node>) -> Vec<Node> {
-    let mut tables: Vec<Node> = Vec::new();
-
-    for node in nodes {
-        if let Node::Table { .. } = node {
-            tables.push(node)
-        }
-    }
-
-    tables
-}
-
-fn get_single_text_node(nodes: &Vec<Node>) -> String {
-    match nodes.as_slice() {
-        [Node::Text { value, .. }] => (*value).to_string(),
-        _ => panic!("Expected exactly one Node::Text variant, got {nodes:#?}"),
+    let wiki_config = match &args.wiki_config {
+        Some(path) => serde_json::from_str(&fs::read_to_string(path).expect("wiki config should be readable"))
+            .expect("wiki config should be valid JSON"),
+        None => WikiConfig::default(),
+    };
+    configuration::set(&wiki_config);
+    let timeline = parse_timeline(&wt, &TextRules::default())?;
+    info!("{} rows in the timeline", timeline.rows.len());
+    for diagnostic in &timeline.diagnostics {
+        warn!("{diagnostic}");
     }
-}
 
-fn parse_title(nodes: &Vec<Node>) {
-    for node in nodes {
-        match node {
-            Node::Template { name, parameters, .. } => {
-                let name = get_single_text_node(name);
-                if name == "StoryCite" {
-                    println!("{parameters:#?}");
-                }
-            }
-            Node::Text { value, .. } => {
-                println!("TEXT: {value:?}");
-            }
-            _ => {
-                panic!("Expected template");
-            }
-        }
+    for row in timeline.rows.iter().take(9) {
+        println!(
+            "{} {} {:?} {}",
+            row.year, row.type_code, row.title_text, row.release_date
+        );
     }
-}
-
-fn reduce_nodes_to_text(nodes: &Vec<Node>) -> String {
-    nodes
-        .iter()
-        .map(|node| match node {
-            Node::Text { value, .. } => value.to_owned().to_owned(),
-            Node::Link { target, text, .. } => reduce_nodes_to_text(text).to_string(),
-            Node::Template { name, parameters, .. } => {
-                // if name == "StoryCite" {
-                // println!("TEMPLATE: {name:?} PARAMS: {parameters:?}");
-                let reduced = reduce_nodes_to_text(name);
-                let name = match name.as_slice() {
-                    [Node::Text { value, .. }] => *value,
-                    _ => panic!("Expected exactly one Node::Text variant, got {name:#?}"),
-                };
 
-                // println!("{reduced:#?} {name:#?}");
-                // }
-                String::from("")
-            }
-            _ => String::from(""),
-        })
-        .collect()
+    Ok(())
 }
 
 #[cfg(test)]
@@ -0,0 +1,45 @@
+#!/usr/bin/env node
+/**
+ * Compares the native parser's direct JS conversion with the neon_serde3 conversion of owned
+ * nodes it replaced, over the wikitext-core fixture corpus.
+ * Usage: node scripts/bench-native.js [--iterations N]
+ *
+ * Build native/index.node in release mode first (npm run build-release in native/).
+ * The Rust side is benchmarked with `cargo bench -p wikitext-core`.
+ */
+
+import fs from "node:fs";
+import path from "node:path";
+import { createRequire } from "node:module";
+
+const require = createRequire(import.meta.url);
+const native = require("../native/index.cjs");
+
+const iterationsArg = process.argv.indexOf("--iterations");
+const iterations = iterationsArg === -1 ? 200 : Number(process.argv[iterationsArg + 1]);
+
+const fixturesDir = path.join(import.meta.dirname, "..", "wikitext-core", "fixtures");
+const options = { constructs: true, strict: false };
+
+/** Median time of a call in milliseconds, after a warmup. */
+function time(fn) {
+  for (let i = 0; i < 10; i++) fn();
+  const times = [];
+  for (let i = 0; i < iterations; i++) {
+    const start = process.hrtime.bigint();
+    fn();
+    times.push(Number(process.hrtime.bigint() - start) / 1e6);
+  }
+  times.sort((a, b) => a - b);
+  return times[Math.floor(times.length / 2)];
+}
+
+for (const name of fs.readdirSync(fixturesDir).filter((name) => name.endsWith(".wiki"))) {
+  const wikitext = fs.readFileSync(path.join(fixturesDir, name), "utf-8");
+  const baseline = time(() => native.parse_baseline(wikitext, options));
+  const direct = time(() => native.parse(wikitext, options));
+  console.log(
+    `${name.padEnd(28)} baseline ${baseline.toFixed(3)} ms  direct ${direct.toFixed(3)} ms  ` +
+      `${(baseline / direct).toFixed(2)}x`,
+  );
+}
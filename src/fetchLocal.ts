@@ -6,10 +6,15 @@
  */
 
 import fs from "node:fs/promises";
+import { createRequire } from "node:module";
 import path from "node:path";
 import { log } from "./util.ts";
 import { FixtureError } from "./errors.ts";
 import type { WookieepediaImageInfoResult, WookieepediaPageResult } from "./types/wookieepedia.ts";
+import type * as Native from "../native/index.cjs";
+
+const require = createRequire(import.meta.url);
+const titleNormalizer = require("../native/index.cjs") as typeof Native;
 
 type TitleInput = string | string[];
 type FixtureResult = WookieepediaPageResult | WookieepediaImageInfoResult;
@@ -48,6 +53,7 @@ async function buildIndex(dir: string): Promise<Map<string, string>> {
       // Index by title (primary key for lookups)
       if (data.title) {
         index.set(data.title, filePath);
+        index.set(normalizeTitle(data.title), filePath);
       }
 
       // Also index by pageid for redundancy
@@ -108,12 +114,12 @@ async function ensureIndexes(legends = false): Promise<void> {
 }
 
 /**
- * Normalize a title for lookup (underscores to spaces, like MediaWiki).
+ * Normalize a title for lookup, following MediaWiki rules (underscores to spaces, first letter capitalized, etc.).
  * @param {string} title - Title to normalize
  * @returns {string} Normalized title
  */
 function normalizeTitle(title: string): string {
-  return title.replace(/_/g, " ");
+  return titleNormalizer.normalize_title(title);
 }
 
 function requireIndex(index: Map<string, string> | null, name: string): Map<string, string> {
@@ -131,7 +137,7 @@ async function loadFixture<T extends FixtureResult>(
   title: string,
   indexes: Map<string, string>[],
 ): Promise<T | null> {
-  // Try both the original title and normalized version (underscores -> spaces)
+  // Try both the original title and normalized version
   const normalized = normalizeTitle(title);
 
   for (const index of indexes) {
@@ -96,6 +96,7 @@ async function main(): Promise<void> {
   log.info(`organism count: ${netLog.organismsCount}`);
   log.info(`c-organism count: ${netLog["c-organismsCount"]}`);
   log.info(`l-organism count: ${netLog["l-organismsCount"]}`);
+  log.info(`unlinked appearance count: ${netLog.unlinkedAppearances}`);
 
   await writePipelineResult(pipelineResult);
   await warnForNewTvShows();
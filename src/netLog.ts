@@ -11,6 +11,7 @@ const netLog = {
   "l-organismsCount": 0,
   "c-creaturesCount": 0,
   "l-creaturesCount": 0,
+  unlinkedAppearances: 0,
 };
 
 export default netLog;
@@ -4,22 +4,12 @@ import { allowedAppCategories } from "../const.ts";
 import netLog from "../netLog.ts";
 import { log } from "../util.ts";
 
-import type {
-  AppearanceTemplateParameter,
-  ArticleAppearances,
-  ParsedAppearanceLink,
-} from "../types/index.ts";
+import type * as Native from "../../native/index.cjs";
+import type { ArticleAppearances } from "../types/index.ts";
 import type { WtfDocument } from "../types/wtf.ts";
 
-type NativeAppearanceParser = {
-  parse_appearances(wikitext: string): {
-    nodes: [{ Template: { parameters: AppearanceTemplateParameter[] } }];
-    links: Record<string, ParsedAppearanceLink[]>;
-  };
-};
-
 const require = createRequire(import.meta.url);
-const appearancesParser = require("../../native/index.cjs") as NativeAppearanceParser;
+const appearancesParser = require("../../native/index.cjs") as typeof Native;
 
 export function getAppearances(doc: WtfDocument): ArticleAppearances | undefined {
   const appsTemplate = doc.templates().find((t) => t.data.template === "app");
@@ -34,26 +24,37 @@ export function getAppearances(doc: WtfDocument): ArticleAppearances | undefined
   }
 
   try {
-    const appsParsed = appearancesParser.parse_appearances(
-      appsTemplate.wikitext().replaceAll(/\n{{!}}\n/g, "\n"),
-    );
+    // Deduplicated, so a target listed twice in a category only gets one appearance entry.
+    const appsParsed = appearancesParser.parse_appearances(appsTemplate.wikitext(), {
+      strict: false,
+      deduplicate: true,
+    });
+    // Problems don't prevent the rest of the template from being used, but they should be fixed on Wookieepedia.
+    for (const diagnostic of appsParsed.diagnostics) {
+      log.error(
+        `Appearances of ${doc.title()} line ${diagnostic.span.line}: ${diagnostic.message}\nErroneous wikitext: ${JSON.stringify(diagnostic.snippet)}\nContext: ${JSON.stringify(diagnostic.context)}`,
+      );
+    }
+
+    // The native parser throws unless the wikitext starts with the template
+    const appsNode = appsParsed.nodes[0];
+    if (!appsNode || !("Template" in appsNode)) {
+      throw new Error("Appearances template node was expected");
+    }
+    const parameters = appsNode.Template.parameters;
 
     // Wookieepedia changed the name of the "creatures" category to "organisms", but some articles still use "creatures".
-    // This changes the new "organisms" category of appearences into the old "creatures" as we wait for Wookieepedia to finish transitioning all articles to "organisms".
-    let creaturesFound = false;
-    let organismsFound = false;
-    for (const category of appsParsed.nodes[0].Template.parameters) {
+    // The native parser merges them in `continuities`; the stored nodes are renamed here.
+    for (const category of parameters) {
       if (["organisms", "c-organisms", "l-organisms"].includes(category.name ?? "")) {
         const countKey = `${category.name}Count`;
         (netLog as Record<string, number>)[countKey] =
           ((netLog as Record<string, number>)[countKey] ?? 0) + 1;
-        organismsFound = true;
       }
       if (["creatures", "c-creatures", "l-creatures"].includes(category.name ?? "")) {
         const countKey = `${category.name}Count`;
         (netLog as Record<string, number>)[countKey] =
           ((netLog as Record<string, number>)[countKey] ?? 0) + 1;
-        creaturesFound = true;
         log.warn(`${doc.title()} contains ${category.name}`);
         category.name = category.name?.replace("creatures", "organisms") ?? null;
       }
@@ -65,27 +66,11 @@ export function getAppearances(doc: WtfDocument): ArticleAppearances | undefined
         log.error(`${doc.title()} contains unknown appearences category: ${category.name}`);
       }
     }
-    if (creaturesFound && organismsFound) {
-      log.error(
-        `'organisms' and 'creatures' coexist in ${doc.title()}. One will get overwritten by the other!`,
-      );
-    }
-    if ("creatures" in appsParsed.links) {
-      appsParsed.links.organisms = appsParsed.links.creatures;
-      delete appsParsed.links.creatures;
-    }
-    if ("c-creatures" in appsParsed.links) {
-      appsParsed.links["c-organisms"] = appsParsed.links["c-creatures"];
-      delete appsParsed.links["c-creatures"];
-    }
-    if ("l-creatures" in appsParsed.links) {
-      appsParsed.links["l-organisms"] = appsParsed.links["l-creatures"];
-      delete appsParsed.links["l-creatures"];
-    }
 
     return {
-      nodes: appsParsed.nodes[0].Template.parameters,
+      nodes: parameters,
       links: appsParsed.links,
+      continuities: appsParsed.continuities,
     };
   } catch (e) {
     const error = e as Error;
@@ -3,3 +3,4 @@ export * from "./infobox.ts";
 export * from "./firstSentence.ts";
 export * from "./categories.ts";
 export * from "./appearances.ts";
+export * from "./timeline.ts";
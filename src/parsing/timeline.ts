@@ -0,0 +1,20 @@
+import { createRequire } from "node:module";
+
+import { log } from "../util.ts";
+
+import type * as Native from "../../native/index.cjs";
+import type { TimelineRow } from "../types/index.ts";
+
+const require = createRequire(import.meta.url);
+const timelineParser = require("../../native/index.cjs") as typeof Native;
+
+/** Rows of the timeline table. Throws if the table is missing or malformed. */
+export function parseTimeline(wikitext: string): TimelineRow[] {
+  const parsed = timelineParser.parse_timeline(wikitext);
+  if (parsed.diagnostics.length > 0) {
+    log.warn(
+      `Timeline has ${parsed.diagnostics.length} wikitext problems, first one on line ${parsed.diagnostics[0]?.span.line}`,
+    );
+  }
+  return parsed.rows;
+}
@@ -12,6 +12,7 @@ import { log } from "../util.ts";
 import { writeFile } from "fs/promises";
 import { cleanupDraft } from "./cleanupDrafts.ts";
 import { allowedAppCategories } from "../const.ts";
+import netLog from "../netLog.ts";
 import { PipelineError } from "../errors.ts";
 import type {
   AppearanceEntry,
@@ -43,16 +44,24 @@ function addAppearances(
   if (!draft.doc) return;
   const appearances = getAppearances(draft.doc);
   draft.appearances = appearances?.nodes;
-  if (!appearances?.links) return;
-
-  for (let [type, links] of Object.entries(appearances.links)) {
-    if (type.startsWith("l-")) continue;
-    if (type.startsWith("c-")) type = type.slice(2);
+  if (!appearances?.continuities) return;
+
+  // Unprefixed categories belong to the article's own continuity, so they count for either run.
+  const { canon, legends, unprefixed } = appearances.continuities;
+  const categories = [
+    ...Object.entries(unprefixed),
+    ...Object.entries(config().LEGENDS ? legends : canon),
+  ];
+  for (const [type, links = []] of categories) {
     for (const link of links) {
+      const unlinked = link.kind === "unlinked";
+      if (unlinked) netLog.unlinkedAppearances++;
       const appearancesForType = (appearancesDrafts[type] ??= {});
-      const linkName = link.name.endsWith("/Legends") ? link.name.slice(0, -8) : link.name;
       const appearanceEntry: AppearanceEntry = {
         id: draft._id,
+        // Entries without an article are listed under their text, e.g. "Unidentified clone trooper"
+        ...(unlinked ? { text: link.text } : {}),
+        ...(link.qualifiers.length ? { q: link.qualifiers } : {}),
         ...(link.templates && {
           t: link.templates.map((t: AppearanceTemplate) => ({
             name: t.name,
@@ -60,12 +69,25 @@ function addAppearances(
           })),
         }),
       };
-      const entries = (appearancesForType[linkName] ??= []);
-      entries.push(appearanceEntry);
+      const entries = (appearancesForType[unlinked ? link.text : link.title] ??= []);
+      // The same target can be listed both unprefixed and prefixed, so its entries are merged
+      const existing = entries.find((entry) => entry.id === draft._id);
+      if (existing) mergeAppearanceEntry(existing, appearanceEntry);
+      else entries.push(appearanceEntry);
     }
   }
 }
 
+function mergeAppearanceEntry(entry: AppearanceEntry, other: AppearanceEntry): void {
+  const sameJson = (a: unknown) => (b: unknown) => JSON.stringify(a) === JSON.stringify(b);
+  for (const qualifier of other.q ?? []) {
+    if (!entry.q?.some(sameJson(qualifier))) (entry.q ??= []).push(qualifier);
+  }
+  for (const template of other.t ?? []) {
+    if (!entry.t?.some(sameJson(template))) (entry.t ??= []).push(template);
+  }
+}
+
 function parseDraftDate(draft: MediaDraft): void {
   try {
     if (draft.dateDetails) {
@@ -19,43 +19,30 @@ export default function timeline(table: TimelineRow[]): MediaDraft[] {
   let drafts: TimelineDraft[] = [];
   let draftMap: Record<string, TimelineDraft> = {}; // Used to find duplicates
 
-  const firstRow = table[0] as Partial<TimelineRow> | undefined;
-
-  if (
-    firstRow?.Title === undefined ||
-    firstRow.col2 === undefined ||
-    firstRow.Released === undefined ||
-    firstRow.Year === undefined
-  ) {
-    throw new PipelineError("Timeline parsing error: Unexpected table layout. Some columns are missing.");
-  }
-
   for (let [i, item] of table.entries()) {
-    const titleLink = item.Title.links?.[0]?.page ?? "";
-    const type = (types as Partial<Record<string, MediaType>>)[item.col2.text];
+    const type = (types as Partial<Record<string, MediaType>>)[item.typeCode];
     if (type === undefined) {
-      if (item.col2.text !== "P")
-        log.warn("Timeline parsing warning: Unknown type, skipping. type: " + item.col2.text);
+      if (item.typeCode !== "P")
+        log.warn("Timeline parsing warning: Unknown type, skipping. type: " + item.typeCode);
       continue;
     }
 
     let draft: TimelineDraft = {
       _id: i,
-      title: decode(titleLink),
+      title: decode(item.titleLink ?? ""),
       type,
-      releaseDate: item.Released.text,
+      releaseDate: item.releaseDate,
       // writer: item["Writer(s)"].links?.map((e) => decode(e.page)) || null,
-      date: decode(item.Year.text) || null,
+      date: decode(item.year) || null,
       chronology: i,
-      titleText: item.Title.text, // For finding duplicates, removed later
+      titleText: item.titleText, // For finding duplicates, removed later
     };
 
-    if (item.col2.text === "JR") draft.fullType = "book-jr";
-    let notes = item.Title.text.split("*");
-    if (notes.length > 1) {
+    if (item.typeCode === "JR") draft.fullType = "book-jr";
+    if (item.notes.length > 0) {
       const timelineNote: ListNode = {
         type: "list",
-        data: notes.slice(1).map((s) => [{ type: "text", text: s.trim() }]),
+        data: item.notes.map((s) => [{ type: "text", text: s }]),
       };
       draft.timelineNotes = [timelineNote]; // TODO:parser get links and such, not just text
 
@@ -67,7 +54,7 @@ export default function timeline(table: TimelineRow[]): MediaDraft[] {
         if (note.includes("adaptation") || note.includes("novelization")) draft.adaptation = true;
       }
     }
-    if (item.Title.text.includes("†")) draft.exactPlacementUnknown = true;
+    if (item.exactPlacementUnknown) draft.exactPlacementUnknown = true;
 
     // Check for duplicate titles - these are usually "chapter" entries, that link to their parent media
     if (draftMap[draft.title]) {
@@ -99,10 +86,10 @@ export default function timeline(table: TimelineRow[]): MediaDraft[] {
     if (!draft.title) {
       log.warn(
         'Timeline parsing warning: Title is empty! setting nopage to true. Title cell:\n"' +
-          item.Title.text +
+          item.titleText +
           '"',
       );
-      draft.title = cleanupTitle(item.Title.text);
+      draft.title = cleanupTitle(item.titleText);
       draft.nopage = true;
     }
 
@@ -22,6 +22,7 @@ import config from "./config.ts";
 import { fetchWookiee } from "./fetchWookiee.ts";
 import { knownTemplates } from "./const.ts";
 import { PipelineError } from "./errors.ts";
+import { parseTimeline } from "./parsing/timeline.ts";
 import { parseTimelineRows } from "./pipeline/timeline.ts";
 import { enrichMediaArticles } from "./pipeline/media.ts";
 import series from "./pipeline/series.ts";
@@ -71,12 +72,13 @@ export async function runPipeline(options: PipelineOptions = {}): Promise<Pipeli
   }
 
   const timelineWikitext = timelineResult.value.wikitext;
-  const timelineDoc = wtf(timelineWikitext);
-  const timelineTable = timelineDoc.tables()[1];
-  if (timelineTable === undefined) {
-    throw new PipelineError(`Timeline table not found on ${timelinePage}`);
+  let data: TimelineRow[];
+  try {
+    data = parseTimeline(timelineWikitext);
+  } catch (e) {
+    throw new PipelineError(`${(e as Error).message} (${timelinePage})`, { cause: e });
   }
-  let data = timelineTable.json() as TimelineRow[];
+  const timelineDoc = wtf(timelineWikitext);
 
   // Verify no unexpected templates
   const templates = Array.from(
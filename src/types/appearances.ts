@@ -1,3 +1,22 @@
+import type {
+  Appearance,
+  Appearances,
+  AppearanceTree,
+  ArticleAppearances,
+  Constructs,
+  ContinuityAppearances,
+  Diagnostic,
+  Qualifier,
+  SectionAppearances,
+  SimpleNode,
+  SimpleParameter,
+  SimpleTable,
+  SimpleTemplate,
+  Span,
+  Tagged,
+  TaggedNode,
+} from "../../native/index.cjs";
+
 /** Categories accepted from the Wookieepedia {{App}} template. */
 export type AppearanceCategory =
   | "characters"
@@ -13,52 +32,60 @@ export type AppearanceCategory =
   | "vehicles"
   | "miscellanea";
 
-export type AppearanceNode =
-  | AppearanceNodeList
-  | AppearanceNodeTemplate
-  | AppearanceNodeLink
-  | AppearanceNodeText;
+/**
+ * Shapes of the native module's output, generated from its Rust types into `native/index.d.cts`.
+ * Nodes are discriminated by object key, not by a `type` field.
+ */
+export type AppearanceNode = Extract<
+  SimpleNode,
+  { List: unknown } | { Template: unknown } | { Link: unknown } | { Text: unknown }
+>;
+export type AppearanceNodeList = Extract<SimpleNode, { List: unknown }>;
+export type AppearanceNodeTemplate = Extract<SimpleNode, { Template: unknown }>;
+export type AppearanceNodeLink = Extract<SimpleNode, { Link: unknown }>;
+export type AppearanceNodeText = Extract<SimpleNode, { Text: unknown }>;
 
-/** Native Rust parser shape: discriminated by object key, not by a `type` field. */
-export type AppearanceNodeList = {
-  List: AppearanceNode[][];
-};
+/** Byte offsets into the parsed wikitext; `line` and `column` are 1-based and point at `start`. */
+export type SourceSpan = Span;
 
-export type AppearanceNodeTemplate = {
-  Template: AppearanceTemplate;
-};
+/** Constructs the native `parse` keeps on top of `AppearanceNode`s; `true` keeps all of them. */
+export type ParseConstructs = Partial<Constructs>;
 
-export type AppearanceNodeLink = {
-  Link: {
-    target: string;
-    text: string;
-  };
-};
+/** Any node returned by the native `parse`, depending on `ParseConstructs`. */
+export type WikitextNode = SimpleNode;
+export type WikitextTable = SimpleTable;
 
-export type AppearanceNodeText = {
-  Text: string;
-};
+export type AppearanceTemplate = SimpleTemplate;
+/** Positional parameters have a null name. */
+export type AppearanceTemplateParameter = SimpleParameter;
 
-export type AppearanceTemplate = {
-  name: string;
-  parameters: AppearanceTemplateParameter[];
-};
+/** Known appearance template such as {{1st}} or {{C|...}}, interpreted by the native module. */
+export type AppearanceQualifier = Qualifier;
 
-export type AppearanceTemplateParameter = {
-  /** Positional parameters are represented as null by the native module. */
-  name: string | null;
-  value: AppearanceNode[];
-};
+export type ParsedAppearanceLink = Appearance;
+export type ParsedAppearanceTree = AppearanceTree;
 
-export type ParsedAppearanceLink = {
-  name: string;
-  templates: AppearanceTemplate[] | null;
-};
+/** Non-fatal parser warning, reported by the native module in lenient mode. */
+export type ParserDiagnostic = Diagnostic;
 
-export type ParsedAppearances = {
-  nodes: AppearanceNode[];
-  links: Record<string, ParsedAppearanceLink[]>;
-};
+/**
+ * Appearances by `c-`/`l-` prefix and canonical category name, with aliases like `creatures`
+ * merged into `organisms`. Unprefixed categories belong to the article's own continuity.
+ */
+export type { ContinuityAppearances };
+
+export type ParsedAppearances = Appearances;
+
+/** An appearances template of a whole article, e.g. one per story of an anthology. */
+export type { SectionAppearances };
+
+export type ParsedArticleAppearances = ArticleAppearances;
+
+/**
+ * Nodes in the `{ type: "internal link", ... }` shape of `AstNode`, returned by the native parser
+ * when called with the `"tagged"` format. `Tagged<T>` is `T` with its nodes in that shape.
+ */
+export type { Tagged, TaggedNode };
 
 export type DraftAppearance = {
   name: string;
@@ -67,6 +94,9 @@ export type DraftAppearance = {
 
 export type AppearanceEntry = {
   id: number;
+  /** Text of an appearance without a link, which has no article of its own. */
+  text?: string;
+  q?: AppearanceQualifier[];
   t?: Array<{
     name: string;
     parameters?: AppearanceTemplateParameter[];
@@ -10,6 +10,7 @@ export type InfoboxData = Partial<Record<keyof InfoboxDraftFields, WtfInfoboxVal
 export type ArticleAppearances = {
   nodes: AppearanceTemplateParameter[];
   links: ParsedAppearances["links"];
+  continuities: ParsedAppearances["continuities"];
 };
 
 export type ParsedArticle = {
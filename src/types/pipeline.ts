@@ -1,3 +1,4 @@
+import type { ParsedTimeline, TimelineRow } from "../../native/index.cjs";
 import type { MediaDraft, SeriesDraft } from "./draft.js";
 import type { AppearancesDrafts } from "./appearances.js";
 import type { MissingMediaDocument } from "./db.js";
@@ -32,14 +33,5 @@ export type MediaStageResult = {
 
 export type PipelineStage<Input, Output> = (input: Input) => Output | Promise<Output>;
 
-export type TimelineCell = {
-  text: string;
-  links?: Array<{ page: string }>;
-};
-
-export type TimelineRow = {
-  Title: TimelineCell;
-  col2: TimelineCell;
-  Released: TimelineCell;
-  Year: TimelineCell;
-};
+/** Row of the timeline table, as returned by the native `parse_timeline`. */
+export type { ParsedTimeline, TimelineRow };
@@ -0,0 +1,102 @@
+/**
+ * Native conversion tests.
+ *
+ * The native module builds its JS objects directly instead of going through neon_serde3.
+ * These tests check both give the same objects, using `parse_baseline` which still converts with
+ * neon_serde3. Requires a built native/index.node.
+ */
+
+import { describe, it, expect } from "vitest";
+import fs from "node:fs";
+import path from "node:path";
+import { createRequire } from "node:module";
+
+const require = createRequire(import.meta.url);
+const native = require("../../native/index.cjs");
+
+// Every node kind, with a redirect that isn't alone to get diagnostics too
+const everyNodeKind = `#REDIRECT [[Yoda]]
+__NOTOC__
+==Plot==
+'''Bold''' ''italic'' '''''both''''' [[Dark Disciple|novel]]s.<ref name="a">[https://starwars.com Official site]</ref>
+<span>open</div>
+<!-- comment -->
+[[File:Yoda.png|thumb|[[Yoda]]]] [[Category:Jedi|Yoda]] {{{1|default}}} {{{2}}}
+*[[A]] {{1st}}
+#[[B]]
+;Term
+:Details
+ preformatted
+Paragraph
+
+break
+----
+{|class="wikitable"
+!Title
+|-
+|[[Yoda]] &amp; {{C|x=[[y]]}}
+|}
+{{App|c-characters=
+*[[Yoda]]|2}}`;
+
+const fixturesDir = path.join(process.cwd(), "wikitext-core", "fixtures");
+const fixtures = fs.readdirSync(fixturesDir).filter((name) => name.endsWith(".wiki"));
+
+// The `type` of every `TaggedNode`
+const nodeKinds = [
+  "bold",
+  "bold italic",
+  "category",
+  "comment",
+  "definition list",
+  "end tag",
+  "external link",
+  "heading",
+  "horizontal divider",
+  "image",
+  "internal link",
+  "italic",
+  "list",
+  "magic word",
+  "paragraph break",
+  "parameter",
+  "preformatted",
+  "redirect",
+  "start tag",
+  "table",
+  "tag",
+  "template",
+  "text",
+];
+
+it("has every node kind in the test wikitext", () => {
+  const kinds = new Set();
+  const walk = (value) => {
+    if (Array.isArray(value)) {
+      value.forEach(walk);
+    } else if (value && typeof value === "object") {
+      if (typeof value.type === "string") kinds.add(value.type);
+      Object.values(value).forEach(walk);
+    }
+  };
+  walk(native.parse(everyNodeKind, { constructs: true, format: "tagged", strict: false }).nodes);
+
+  expect([...kinds].sort()).toEqual(nodeKinds);
+});
+
+describe.each(["external", "tagged"])("%s format", (format) => {
+  it.each([true, {}])("converts every node kind like neon_serde3 (constructs: %j)", (constructs) => {
+    const options = { constructs, format, strict: false };
+    const direct = native.parse(everyNodeKind, options);
+
+    expect(direct.diagnostics).not.toHaveLength(0);
+    expect(direct).toStrictEqual(native.parse_baseline(everyNodeKind, options));
+  });
+
+  it.each(fixtures)("converts %s like neon_serde3", (name) => {
+    const wikitext = fs.readFileSync(path.join(fixturesDir, name), "utf-8");
+    const options = { constructs: true, format, strict: false };
+
+    expect(native.parse(wikitext, options)).toStrictEqual(native.parse_baseline(wikitext, options));
+  });
+});
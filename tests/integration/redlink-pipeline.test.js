@@ -78,26 +78,24 @@ async function* pageGenerator(titles) {
   }
 }
 
+function mockTimelineRow(year, typeCode, title, releaseDate) {
+  return {
+    year,
+    typeCode,
+    titleLink: title,
+    titleText: title,
+    titleNodes: [],
+    notes: [],
+    exactPlacementUnknown: false,
+    releaseDate,
+    span: { start: 0, end: 0, line: 1, column: 1 },
+  };
+}
+
 function mockTimelineRows() {
   return [
-    {
-      Title: { text: "Title" },
-      col2: { text: "" },
-      Released: { text: "Released" },
-      Year: { text: "Year" },
-    },
-    {
-      Title: { text: "Existing Comic", links: [{ page: "Existing Comic" }] },
-      col2: { text: "C" },
-      Released: { text: "January 1, 2020" },
-      Year: { text: "1 ABY" },
-    },
-    {
-      Title: { text: "Missing Comic", links: [{ page: "Missing Comic" }] },
-      col2: { text: "C" },
-      Released: { text: "January 2, 2020" },
-      Year: { text: "2 ABY" },
-    },
+    mockTimelineRow("1 ABY", "C", "Existing Comic", "January 1, 2020"),
+    mockTimelineRow("2 ABY", "C", "Missing Comic", "January 2, 2020"),
   ];
 }
 
@@ -8,7 +8,6 @@
 import { describe, it, expect, beforeAll, vi } from "vitest";
 import fs from "node:fs/promises";
 import path from "node:path";
-import wtf from "wtf_wikipedia";
 
 // Mock the log to avoid console output
 vi.mock("../../src/util.ts", async (importOriginal) => {
@@ -26,23 +25,19 @@ vi.mock("../../src/util.ts", async (importOriginal) => {
 
 // Import after mocking
 import timeline from "../../src/pipeline/timeline.ts";
-import initWtf from "../../src/initWtf.ts";
+import { parseTimeline } from "../../src/parsing/timeline.ts";
 
 describe("timeline pipeline stage", () => {
   let timelineData;
   let drafts;
 
   beforeAll(async () => {
-    // Initialize wtf_wikipedia with custom templates
-    initWtf();
-
     // Load timeline fixture (live fixtures, not snapshotted)
     const timelinePath = path.join(process.cwd(), "fixtures", "canon", "timeline.json");
     const timelineJson = JSON.parse(await fs.readFile(timelinePath, "utf-8"));
 
-    // Parse the timeline wikitext
-    const doc = wtf(timelineJson.wikitext);
-    timelineData = doc.tables()[1].json();
+    // Parse the timeline wikitext with the native parser
+    timelineData = parseTimeline(timelineJson.wikitext);
 
     // Run the timeline stage
     drafts = timeline(timelineData);
@@ -96,6 +91,19 @@ describe("timeline pipeline stage", () => {
         expect(draft.chronology).toBe(draft._id);
       }
     });
+
+    it("_id is the index of the row below the header, as with the wtf table rows", () => {
+      for (const draft of drafts) {
+        expect(timelineData[draft._id].releaseDate).toBe(draft.releaseDate);
+      }
+    });
+
+    it("titles don't keep the anchor of their link", () => {
+      for (const draft of drafts) {
+        expect(draft.title).not.toContain("#");
+        if (draft.href) expect(draft.href).not.toContain("#");
+      }
+    });
   });
 
   describe("known media parsing", () => {
@@ -0,0 +1,176 @@
+//! Parsing benchmarks over the fixture corpus, run with `cargo bench -p wikitext-core`.
+//! `to_value` compares the borrowed nodes with the owned nodes they replaced, see `baseline`.
+
+use std::fs;
+
+use divan::Bencher;
+use wikitext_core::appearances::{parse_appearances, parse_article_appearances};
+use wikitext_core::constructs::Constructs;
+use wikitext_core::options::ParseOptions;
+use wikitext_core::parse_document;
+use wikitext_core::text::TextRules;
+use wikitext_core::timeline::parse_timeline;
+
+const FIXTURES: &[&str] = &[
+    "app-dark-disciple",
+    "app-spacing",
+    "article-dark-disciple",
+    "timeline-excerpt",
+];
+
+fn main() {
+    divan::main();
+}
+
+fn fixture(name: &str) -> String {
+    let path = format!("{}/fixtures/{name}.wiki", env!("CARGO_MANIFEST_DIR"));
+    fs::read_to_string(&path).unwrap_or_else(|error| panic!("{path}: {error}"))
+}
+
+#[divan::bench(args = FIXTURES)]
+fn document(bencher: Bencher, name: &str) {
+    let wikitext = fixture(name);
+    bencher.bench(|| parse_document(&wikitext, &Constructs::all()));
+}
+
+/// Parsing and serializing, the work behind a call from Node.
+#[divan::bench(args = FIXTURES)]
+fn document_to_json(bencher: Bencher, name: &str) {
+    let wikitext = fixture(name);
+    bencher.bench(|| serde_json::to_string(&parse_document(&wikitext, &Constructs::all())));
+}
+
+/// Parsing and converting to a tree of values, which stands in for the JS objects built for Node.
+#[divan::bench_group]
+mod to_value {
+    use super::*;
+
+    #[divan::bench(args = FIXTURES)]
+    fn baseline(bencher: Bencher, name: &str) {
+        let wikitext = fixture(name);
+        bencher.bench(|| serde_json::to_value(super::baseline::parse(&wikitext)).unwrap());
+    }
+
+    #[divan::bench(args = FIXTURES)]
+    fn borrowed(bencher: Bencher, name: &str) {
+        let wikitext = fixture(name);
+        bencher.bench(|| {
+            serde_json::to_value(parse_document(&wikitext, &Constructs::default())).unwrap()
+        });
+    }
+}
+
+#[divan::bench(args = ["app-dark-disciple", "app-spacing"])]
+fn appearances(bencher: Bencher, name: &str) {
+    let wikitext = fixture(name);
+    let options = ParseOptions::default();
+    bencher.bench(|| parse_appearances(&wikitext, &options).unwrap());
+}
+
+#[divan::bench]
+fn article_appearances(bencher: Bencher) {
+    let wikitext = fixture("article-dark-disciple");
+    let options = ParseOptions::default();
+    bencher.bench(|| parse_article_appearances(&wikitext, &options).unwrap());
+}
+
+#[divan::bench]
+fn timeline(bencher: Bencher) {
+    let wikitext = fixture("timeline-excerpt");
+    let rules = TextRules::default();
+    bencher.bench(|| parse_timeline(&wikitext, &rules).unwrap());
+}
+
+/// The node tree as it was before the borrowed nodes: every string is copied, and each template
+/// parameter is parsed again with a new `Configuration`.
+mod baseline {
+    use parse_wiki_text::{Configuration, ListItem, Node};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    pub struct SimpleTemplate {
+        name: String,
+        parameters: Vec<SimpleParameter>,
+    }
+
+    #[derive(Serialize)]
+    pub enum SimpleNode {
+        List(Vec<Vec<SimpleNode>>),
+        Template(SimpleTemplate),
+        Link { target: String, text: String },
+        Text(String),
+    }
+
+    #[derive(Serialize)]
+    pub struct SimpleParameter {
+        name: Option<String>,
+        value: Vec<SimpleNode>,
+    }
+
+    pub fn parse(wikitext: &str) -> Vec<SimpleNode> {
+        parse_nodes(&Configuration::default().parse(wikitext).nodes, wikitext)
+    }
+
+    fn reduce_nodes_to_text(nodes: &[Node]) -> String {
+        nodes
+            .iter()
+            .map(|node| match node {
+                Node::Text { value, .. } => value,
+                _ => "",
+            })
+            .collect()
+    }
+
+    fn parse_list_items(items: &[ListItem], wikitext: &str) -> SimpleNode {
+        SimpleNode::List(
+            items
+                .iter()
+                .map(|item| parse_nodes(&item.nodes, wikitext))
+                .collect(),
+        )
+    }
+
+    fn parse_nodes(nodes: &[Node], wikitext: &str) -> Vec<SimpleNode> {
+        let mut node_list = Vec::new();
+        for node in nodes {
+            match node {
+                Node::Link { target, text, .. } => node_list.push(SimpleNode::Link {
+                    target: target.to_string(),
+                    text: reduce_nodes_to_text(text),
+                }),
+                Node::UnorderedList { items, .. } => {
+                    node_list.push(parse_list_items(items, wikitext))
+                }
+                Node::Text { value, .. } => node_list.push(SimpleNode::Text(value.to_string())),
+                Node::CharacterEntity { character, .. } => {
+                    node_list.push(SimpleNode::Text(character.to_string()))
+                }
+                Node::Template {
+                    name, parameters, ..
+                } => node_list.push(SimpleNode::Template(SimpleTemplate {
+                    name: reduce_nodes_to_text(name),
+                    parameters: parameters
+                        .iter()
+                        .map(|param| {
+                            let name = param.name.as_ref().map(|name| reduce_nodes_to_text(name));
+                            let start = match &name {
+                                Some(name) => param.start + name.len() + 1,
+                                None => param.start,
+                            };
+                            let param_wt = &wikitext[start..param.end];
+                            SimpleParameter {
+                                name,
+                                value: parse_nodes(
+                                    &Configuration::default().parse(param_wt).nodes,
+                                    param_wt,
+                                ),
+                            }
+                        })
+                        .collect(),
+                })),
+                _ => (),
+            }
+        }
+        node_list
+    }
+}
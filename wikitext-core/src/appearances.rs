@@ -0,0 +1,1071 @@
+//! Appearances listed in the `{{App}}` templates of an article.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::categories::{CategoryAliases, ContinuityAppearances};
+use crate::constructs::Constructs;
+use crate::diagnostics::Diagnostic;
+use crate::options::ParseOptions;
+use crate::qualifiers::Qualifier;
+use crate::sections::find_section_templates;
+use crate::serialize::template_to_wikitext;
+use crate::span::Span;
+use crate::title::{self, Title};
+use crate::{owned_nodes, parse_checked, Error, Result, SimpleNode, SimpleTemplate};
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct Appearances<'a> {
+    pub nodes: Vec<SimpleNode<'a>>,
+    /// Appearances by category, each listed once per target when deduplicated.
+    pub links: HashMap<String, Vec<Appearance<'a>>>,
+    /// Same appearances as `links`, nested the way the wikitext lists are.
+    pub tree: HashMap<String, Vec<AppearanceTree<'a>>>,
+    /// Same appearances as `links`, by continuity and canonical category name.
+    pub continuities: ContinuityAppearances<'a>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Appearances<'_> {
+    /// Copy that doesn't borrow from the wikitext it was parsed from.
+    pub fn into_owned(self) -> Appearances<'static> {
+        Appearances {
+            nodes: owned_nodes(self.nodes),
+            links: owned_categories(self.links),
+            tree: self
+                .tree
+                .into_iter()
+                .map(|(name, trees)| {
+                    let trees = trees.into_iter().map(AppearanceTree::into_owned).collect();
+                    (name, trees)
+                })
+                .collect(),
+            continuities: self.continuities.into_owned(),
+            diagnostics: self.diagnostics,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum AppearanceKind {
+    Link,
+    /// List entry without a link, e.g. `*Unidentified clone trooper {{1st}}`. Its name is the entry's text.
+    Unlinked,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct Appearance<'a> {
+    pub kind: AppearanceKind,
+    /// Normalized link target, e.g. `Coruscant/Legends#Senate District`.
+    pub name: Cow<'a, str>,
+    /// Page title without the anchor and the `/Legends` suffix, e.g. `Coruscant`.
+    pub title: Cow<'a, str>,
+    pub anchor: Option<String>,
+    /// Text shown for the link, e.g. `1138` for `[[1138 (Geonosis)|1138]]`.
+    pub text: Cow<'a, str>,
+    /// Whether the link points to the Legends version of the page.
+    pub legends: bool,
+    pub templates: Option<Vec<SimpleTemplate<'a>>>,
+    /// Known templates from `templates`, e.g. `{{1st}}`.
+    pub qualifiers: Vec<Qualifier>,
+    /// Templates from `templates` that aren't qualifiers.
+    pub unknown_templates: Vec<SimpleTemplate<'a>>,
+    pub span: Span,
+    /// Name of the appearance this one is listed under, e.g. the war of a battle.
+    pub parent: Option<Cow<'a, str>>,
+    /// How many times the appearance is listed in its category, 1 unless deduplicated.
+    pub occurrences: usize,
+}
+
+impl Appearance<'_> {
+    pub fn into_owned(self) -> Appearance<'static> {
+        let owned_templates = |templates: Vec<SimpleTemplate>| {
+            templates
+                .into_iter()
+                .map(SimpleTemplate::into_owned)
+                .collect()
+        };
+        Appearance {
+            kind: self.kind,
+            name: Cow::Owned(self.name.into_owned()),
+            title: Cow::Owned(self.title.into_owned()),
+            anchor: self.anchor,
+            text: Cow::Owned(self.text.into_owned()),
+            legends: self.legends,
+            templates: self.templates.map(owned_templates),
+            qualifiers: self.qualifiers,
+            unknown_templates: owned_templates(self.unknown_templates),
+            span: self.span,
+            parent: self.parent.map(|parent| Cow::Owned(parent.into_owned())),
+            occurrences: self.occurrences,
+        }
+    }
+}
+
+/// Appearances by category that don't borrow from the wikitext they were parsed from.
+pub(crate) fn owned_categories(
+    categories: HashMap<String, Vec<Appearance>>,
+) -> HashMap<String, Vec<Appearance<'static>>> {
+    categories
+        .into_iter()
+        .map(|(name, links)| {
+            (
+                name,
+                links.into_iter().map(Appearance::into_owned).collect(),
+            )
+        })
+        .collect()
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct AppearanceTree<'a> {
+    #[serde(flatten)]
+    pub appearance: Appearance<'a>,
+    pub children: Vec<AppearanceTree<'a>>,
+}
+
+const LEGENDS_SUFFIX: &str = "/Legends";
+/// Wookieepedia's appearances template, `{{App}}`.
+pub const APPEARANCES_TEMPLATE: &str = "App";
+
+impl<'a> AppearanceTree<'a> {
+    fn link(target: &Cow<'a, str>, text: &Cow<'a, str>, span: Span) -> Self {
+        let anchor = match target.contains('#') {
+            true => Title::parse(target).anchor,
+            false => None,
+        };
+        let page = match target {
+            Cow::Borrowed(target) => title::normalize_title(target),
+            Cow::Owned(target) => Cow::Owned(title::normalize_title(target).into_owned()),
+        };
+        let title_len = page.strip_suffix(LEGENDS_SUFFIX).map(str::len);
+        let legends = title_len.is_some();
+        let title = match title_len {
+            Some(len) => truncate(page, len),
+            None => page,
+        };
+        Self::new(
+            AppearanceKind::Link,
+            target.clone(),
+            title,
+            anchor,
+            text.clone(),
+            legends,
+            span,
+        )
+    }
+
+    fn unlinked(text: &str, span: Span) -> Self {
+        Self::new(
+            AppearanceKind::Unlinked,
+            Cow::Owned(text.to_string()),
+            Cow::Owned(text.to_string()),
+            None,
+            Cow::Owned(text.to_string()),
+            false,
+            span,
+        )
+    }
+
+    fn new(
+        kind: AppearanceKind,
+        name: Cow<'a, str>,
+        title: Cow<'a, str>,
+        anchor: Option<String>,
+        text: Cow<'a, str>,
+        legends: bool,
+        span: Span,
+    ) -> Self {
+        AppearanceTree {
+            appearance: Appearance {
+                kind,
+                name,
+                title,
+                anchor,
+                text,
+                legends,
+                templates: None,
+                qualifiers: Vec::new(),
+                unknown_templates: Vec::new(),
+                span,
+                parent: None,
+                occurrences: 1,
+            },
+            children: Vec::new(),
+        }
+    }
+
+    pub fn into_owned(self) -> AppearanceTree<'static> {
+        AppearanceTree {
+            appearance: self.appearance.into_owned(),
+            children: self
+                .children
+                .into_iter()
+                .map(AppearanceTree::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// First `len` bytes of `text`, still borrowed if `text` is.
+fn truncate(text: Cow<str>, len: usize) -> Cow<str> {
+    match text {
+        Cow::Borrowed(text) => Cow::Borrowed(&text[..len]),
+        Cow::Owned(mut text) => {
+            text.truncate(len);
+            Cow::Owned(text)
+        }
+    }
+}
+
+/// Text an entry starts with, if the entry has no link to name it.
+fn unlinked_appearance<'a>(nodes: &[SimpleNode<'a>]) -> Option<AppearanceTree<'a>> {
+    if nodes
+        .iter()
+        .any(|node| matches!(node, SimpleNode::Link { .. }))
+    {
+        return None;
+    }
+    let mut name = String::new();
+    let mut span: Option<Span> = None;
+    for node in nodes {
+        let SimpleNode::Text {
+            value,
+            span: text_span,
+        } = node
+        else {
+            break;
+        };
+        name.push_str(value);
+        span = Some(match span {
+            Some(span) => Span {
+                end: text_span.end,
+                ..span
+            },
+            None => *text_span,
+        });
+    }
+    let name = name.trim();
+    match span {
+        Some(span) if !name.is_empty() => Some(AppearanceTree::unlinked(name, span)),
+        _ => None,
+    }
+}
+
+pub fn collect_appearance_tree<'a>(nodes: &[SimpleNode<'a>]) -> Vec<AppearanceTree<'a>> {
+    let mut appearances: Vec<AppearanceTree> = unlinked_appearance(nodes).into_iter().collect();
+    // Last appearance named directly in `nodes`, rather than in a list among them
+    let mut named = appearances.len().checked_sub(1);
+    // Templates before the first appearance, e.g. `*{{1st}} [[Yoda]]`, qualify the one after them
+    let mut leading = Vec::new();
+    for node in nodes {
+        match node {
+            SimpleNode::Link { target, text, span } => {
+                named = Some(appearances.len());
+                let mut tree = AppearanceTree::link(target, text, *span);
+                for template in leading.drain(..) {
+                    add_template(&mut tree.appearance, template);
+                }
+                appearances.push(tree)
+            }
+            SimpleNode::List { items, .. } => {
+                let mut nested: Vec<AppearanceTree> = items
+                    .iter()
+                    .flat_map(|item| collect_appearance_tree(item))
+                    .collect();
+                // A list following an appearance in the same list item is nested under it,
+                // lists next to each other, e.g. split by a blank line, are siblings
+                match named.map(|index| &mut appearances[index]) {
+                    Some(parent) => {
+                        for child in &mut nested {
+                            child.appearance.parent = Some(parent.appearance.name.clone());
+                        }
+                        parent.children.append(&mut nested);
+                    }
+                    None => appearances.append(&mut nested),
+                }
+            }
+            SimpleNode::Template(template) => match appearances.last_mut() {
+                Some(last) => add_template(&mut last.appearance, template),
+                // Templates on their own, e.g. a `{{C|...}}` line, have nothing to qualify
+                None => leading.push(template),
+            },
+            _ => (),
+        }
+    }
+    appearances
+}
+
+fn add_template<'a>(appearance: &mut Appearance<'a>, template: &SimpleTemplate<'a>) {
+    match Qualifier::from_template(template) {
+        Some(qualifier) => appearance.qualifiers.push(qualifier),
+        None => appearance.unknown_templates.push(template.clone()),
+    }
+    appearance
+        .templates
+        .get_or_insert_with(Vec::new)
+        .push(template.clone());
+}
+
+/// Lists the appearances of a tree depth-first, in the order they appear in the wikitext.
+pub fn flatten_appearance_tree<'a>(trees: &[AppearanceTree<'a>]) -> Vec<Appearance<'a>> {
+    let mut appearances = Vec::new();
+    for tree in trees {
+        appearances.push(tree.appearance.clone());
+        appearances.append(&mut flatten_appearance_tree(&tree.children));
+    }
+    appearances
+}
+
+/// Merges appearances of the same page into the first one, so `[[Yoda#Legacy]]` and `[[Yoda]]`
+/// are one appearance: its span and parent are kept, the templates and qualifiers of the others
+/// are added unless it has them already, and its occurrences counted.
+pub fn deduplicate_appearances(appearances: Vec<Appearance>) -> Vec<Appearance> {
+    let mut unique: Vec<Appearance> = Vec::new();
+    let mut indexes = HashMap::new();
+    for appearance in appearances {
+        let page = appearance.name.split('#').next().unwrap_or_default();
+        let key = (appearance.kind, page.to_string());
+        let Some(&index) = indexes.get(&key) else {
+            indexes.insert(key, unique.len());
+            unique.push(appearance);
+            continue;
+        };
+        let first = &mut unique[index];
+        first.occurrences += appearance.occurrences;
+        for qualifier in appearance.qualifiers {
+            if !first.qualifiers.contains(&qualifier) {
+                first.qualifiers.push(qualifier);
+            }
+        }
+        if let Some(templates) = appearance.templates {
+            merge_templates(first.templates.get_or_insert_with(Vec::new), templates);
+        }
+        merge_templates(&mut first.unknown_templates, appearance.unknown_templates);
+    }
+    unique
+}
+
+/// Adds the templates not in `templates` yet, compared without their spans.
+fn merge_templates<'a>(templates: &mut Vec<SimpleTemplate<'a>>, others: Vec<SimpleTemplate<'a>>) {
+    let mut known: Vec<String> = templates.iter().map(template_to_wikitext).collect();
+    for template in others {
+        let wikitext = template_to_wikitext(&template);
+        if !known.contains(&wikitext) {
+            known.push(wikitext);
+            templates.push(template);
+        }
+    }
+}
+
+/// Appearances listed in the parameters of an `{{App}}` template, see `Appearances`.
+#[derive(Serialize, Debug)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct TemplateAppearances<'a> {
+    pub links: HashMap<String, Vec<Appearance<'a>>>,
+    pub tree: HashMap<String, Vec<AppearanceTree<'a>>>,
+    pub continuities: ContinuityAppearances<'a>,
+}
+
+/// With `deduplicate`, each target is listed once per category in `links` and `continuities`;
+/// `tree` keeps them all.
+pub fn template_appearances<'a>(
+    template: &SimpleTemplate<'a>,
+    deduplicate: bool,
+    aliases: &CategoryAliases,
+) -> Result<TemplateAppearances<'a>> {
+    let mut ret = TemplateAppearances {
+        links: HashMap::new(),
+        tree: HashMap::new(),
+        continuities: ContinuityAppearances::default(),
+    };
+    for param in &template.parameters {
+        if let Some(name) = &param.name {
+            let tree = collect_appearance_tree(&param.value);
+            let mut links = flatten_appearance_tree(&tree);
+            let (continuity, category) = aliases.resolve(name);
+            ret.continuities
+                .get_mut(continuity)
+                .entry(category)
+                .or_default()
+                .extend(links.iter().cloned());
+            if deduplicate {
+                links = deduplicate_appearances(links);
+            }
+            ret.links.insert(name.to_string(), links);
+            ret.tree.insert(name.to_string(), tree);
+        } else {
+            return Err(Error::UnnamedParameter);
+        }
+    }
+    if deduplicate {
+        // Again, since aliased categories were merged
+        for links in ret.continuities.categories_mut() {
+            *links = deduplicate_appearances(std::mem::take(links));
+        }
+    }
+    Ok(ret)
+}
+
+/// Appearances of a wikitext that is a single appearances template, see `ParseOptions` for the
+/// templates accepted.
+pub fn parse_appearances<'a>(wikitext: &'a str, options: &ParseOptions) -> Result<Appearances<'a>> {
+    let parsed = parse_checked(wikitext, options)?;
+    let Some(SimpleNode::Template(template)) = parsed.nodes.first() else {
+        return Err(Error::NotATemplate);
+    };
+    if let Some(names) = &options.template_names {
+        if !names
+            .iter()
+            .any(|name| title::normalize_template_name(name) == template.name)
+        {
+            return Err(Error::UnexpectedTemplate(template.name.to_string()));
+        }
+    }
+    let TemplateAppearances {
+        links,
+        tree,
+        continuities,
+    } = template_appearances(template, options.deduplicate, &options.category_aliases)?;
+    Ok(Appearances {
+        nodes: parsed.nodes,
+        links,
+        tree,
+        continuities,
+        diagnostics: parsed.diagnostics,
+    })
+}
+
+/// Appearances template of an article and the section it is in.
+#[derive(Serialize, Debug)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct SectionAppearances<'a> {
+    /// `path` joined with ` > `, unique within the article: later templates under the same
+    /// headings get ` #2`, ` #3` and so on. Empty before the first heading.
+    pub key: String,
+    /// Nearest heading above the template, `None` before the first heading.
+    pub heading: Option<String>,
+    /// Headings enclosing the template, outermost first, e.g. the story of an anthology.
+    pub path: Vec<String>,
+    pub template: SimpleTemplate<'a>,
+    pub appearances: TemplateAppearances<'a>,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct ArticleAppearances<'a> {
+    /// In article order. A list rather than a map by heading, since an article can have
+    /// templates before its first heading and under repeated headings, like the `Appearances`
+    /// of each story in an anthology. Look them up by `key` with `section`.
+    pub sections: Vec<SectionAppearances<'a>>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> ArticleAppearances<'a> {
+    pub fn section(&self, key: &str) -> Option<&SectionAppearances<'a>> {
+        self.sections.iter().find(|section| section.key == key)
+    }
+}
+
+/// Appearances of every appearances template in a whole article, in the order they appear.
+pub fn parse_article_appearances<'a>(
+    wikitext: &'a str,
+    options: &ParseOptions,
+) -> Result<ArticleAppearances<'a>> {
+    // Tags are kept for templates inside e.g. `<tabber>`
+    let options = ParseOptions {
+        constructs: Constructs {
+            headings: true,
+            tags: true,
+            ..options.constructs
+        },
+        ..options.clone()
+    };
+    let parsed = parse_checked(wikitext, &options)?;
+    let default_names = [APPEARANCES_TEMPLATE.to_string()];
+    let names = options.template_names.as_deref().unwrap_or(&default_names);
+    let mut keys = HashSet::new();
+    let sections = find_section_templates(&parsed.nodes, names)
+        .into_iter()
+        .map(|found| {
+            let path_key = found.path.join(" > ");
+            let key = (1..)
+                .map(|n| match n {
+                    1 => path_key.clone(),
+                    n => format!("{path_key} #{n}"),
+                })
+                .find(|key| !keys.contains(key))
+                .unwrap();
+            keys.insert(key.clone());
+            Ok(SectionAppearances {
+                key,
+                heading: found.path.last().cloned(),
+                appearances: template_appearances(
+                    found.template,
+                    options.deduplicate,
+                    &options.category_aliases,
+                )?,
+                path: found.path,
+                template: found.template.clone(),
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(ArticleAppearances {
+        sections,
+        diagnostics: parsed.diagnostics,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use parse_wiki_text::Configuration;
+
+    use super::*;
+    use crate::span::LineIndex;
+    use crate::{diagnostics, parse_document, parse_nodes};
+
+    #[test]
+    fn test_ordered_appearance_list() {
+        let wt = "{{App\n|c-characters=\n#[[Yoda]]\n#[[Dooku]] {{Mo}}\n}}";
+        let appearances = parse_appearances(wt, &ParseOptions::default()).unwrap();
+        let links = &appearances.links["c-characters"];
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[1].qualifiers, [Qualifier::MentionedOnly]);
+    }
+
+    #[test]
+    fn test_preprocessed_appearances() {
+        let wt = "{{App\n|c-characters=\n*[[Yoda]]\n<!-- Order by importance -->\n{{!}}\n*[[Dooku]] {{Mo}}<noinclude>\n</noinclude>}}";
+        let appearances = parse_appearances(wt, &ParseOptions::default()).unwrap();
+        let links = &appearances.links["c-characters"];
+        assert_eq!(links.len(), 2);
+        let dooku = &links[1];
+        assert_eq!(&wt[dooku.span.start..dooku.span.end], "[[Dooku]]");
+        assert_eq!(dooku.span.line, 6);
+    }
+
+    #[test]
+    fn test_deduplicated_appearances() {
+        let wt = "{{App\n|c-characters=\n*[[Dooku]] {{1st}}\n*[[Yoda]]\n*[[Dooku|Tyranus]] {{C|hologram}}\n*[[Dooku]] {{1st}}\n*[[Yoda#Legacy]]\n}}";
+        let appearances = parse_appearances(
+            wt,
+            &ParseOptions {
+                deduplicate: true,
+                ..ParseOptions::default()
+            },
+        )
+        .unwrap();
+        let links = &appearances.links["c-characters"];
+        assert_eq!(links.len(), 2);
+        let dooku = &links[0];
+        assert_eq!(dooku.occurrences, 3);
+        assert_eq!(dooku.span.line, 3);
+        assert_eq!(dooku.qualifiers.len(), 2);
+        // The second `{{1st}}` is the same template
+        assert_eq!(dooku.templates.as_ref().unwrap().len(), 2);
+        // Links to a section are appearances of the page
+        assert_eq!(links[1].occurrences, 2);
+        // The tree still lists every entry
+        assert_eq!(appearances.tree["c-characters"].len(), 5);
+
+        let appearances = parse_appearances(wt, &ParseOptions::default()).unwrap();
+        assert_eq!(appearances.links["c-characters"].len(), 5);
+    }
+
+    #[test]
+    fn test_continuities() {
+        let wt = "{{App\n|c-creatures=\n*[[Rancor]]\n|c-organisms=\n*[[Nexu]]\n*[[Rancor]]\n|l-characters=\n*[[Yoda/Legends|Yoda]]\n|droids=\n*[[R2-D2]]\n}}";
+        let appearances = parse_appearances(
+            wt,
+            &ParseOptions {
+                deduplicate: true,
+                ..ParseOptions::default()
+            },
+        )
+        .unwrap();
+        let continuities = &appearances.continuities;
+        let organisms = &continuities.canon["organisms"];
+        assert_eq!(organisms.len(), 2);
+        assert_eq!(organisms[0].occurrences, 2);
+        assert!(!continuities.canon.contains_key("creatures"));
+        let yoda = &continuities.legends["characters"][0];
+        assert_eq!((yoda.title.as_ref(), yoda.legends), ("Yoda", true));
+        assert_eq!(continuities.unprefixed["droids"].len(), 1);
+        // `links` keeps the categories as written
+        assert!(appearances.links.contains_key("c-creatures"));
+    }
+
+    #[test]
+    fn test_appearances_options() {
+        let wt = "{{App\n|c-characters=\n*[[Yoda]]\n*[[Dooku]]\n}}";
+        let only = |names: &[&str]| ParseOptions {
+            template_names: Some(names.iter().map(|name| name.to_string()).collect()),
+            ..ParseOptions::default()
+        };
+        assert!(parse_appearances(wt, &only(&["app", "Appearances"])).is_ok());
+        assert!(matches!(
+            parse_appearances(wt, &only(&["Appearances"])),
+            Err(Error::UnexpectedTemplate(name)) if name == "App"
+        ));
+
+        // Without reparsing, the list is left as text around the links
+        let options = ParseOptions {
+            reparse_parameters: false,
+            ..ParseOptions::default()
+        };
+        let appearances = parse_appearances(wt, &options).unwrap();
+        let SimpleNode::Template(template) = &appearances.nodes[0] else {
+            panic!("expected a template, got {:?}", appearances.nodes[0]);
+        };
+        let value = &template.parameters[0].value;
+        assert!(!value
+            .iter()
+            .any(|node| matches!(node, SimpleNode::List { .. })));
+        assert_eq!(appearances.links["c-characters"].len(), 2);
+    }
+
+    #[test]
+    fn test_article_appearances() {
+        let wt = "'''Tales''' is an anthology.\n== Stories ==\n=== The Clone ===\n{{App\n|c-characters=\n*[[Rex]]\n}}\n=== The Droid ===\n{{App\n|c-droids=\n*[[R2-D2]]\n}}\n== Sources ==\n";
+        let article = parse_article_appearances(wt, &ParseOptions::default()).unwrap();
+        assert_eq!(article.sections.len(), 2);
+        let droid = article.section("Stories > The Droid").unwrap();
+        assert_eq!(droid.heading.as_deref(), Some("The Droid"));
+        assert_eq!(droid.path, ["Stories", "The Droid"]);
+        assert_eq!(droid.appearances.links["c-droids"][0].name, "R2-D2");
+
+        // Repeated headings, and templates before any heading, still get a key each
+        let wt = "{{App\n|c-characters=\n*[[Rex]]\n}}\n== Appearances ==\n{{App\n|c-characters=\n*[[Yoda]]\n}}\n{{App\n|c-droids=\n*[[R2-D2]]\n}}\n== Appearances ==\n{{App\n|c-characters=\n*[[Dooku]]\n}}";
+        let article = parse_article_appearances(wt, &ParseOptions::default()).unwrap();
+        let keys: Vec<_> = article
+            .sections
+            .iter()
+            .map(|section| &section.key)
+            .collect();
+        assert_eq!(
+            keys,
+            ["", "Appearances", "Appearances #2", "Appearances #3"]
+        );
+        let dooku = article.section("Appearances #3").unwrap();
+        assert_eq!(dooku.appearances.links["c-characters"][0].name, "Dooku");
+
+        let malformed = "== Appearances ==\n{{App\n|[[Rex]]\n}}";
+        assert!(parse_article_appearances(malformed, &ParseOptions::default()).is_err());
+    }
+
+    /// Every `app-*` fixture is read with trimmed parameter names and link spans on their links.
+    #[test]
+    fn test_app_corpus() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+        let mut count = 0;
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if !path
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("app-")
+            {
+                continue;
+            }
+            let wt = fs::read_to_string(&path).unwrap();
+            let appearances = parse_appearances(&wt, &ParseOptions::default())
+                .unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+            for (name, links) in &appearances.links {
+                assert_eq!(name.trim(), name);
+                assert!(!links.is_empty(), "{} of {}", name, path.display());
+                for link in links {
+                    let source = &wt[link.span.start..link.span.end];
+                    assert_eq!(
+                        source.starts_with("[["),
+                        matches!(link.kind, AppearanceKind::Link),
+                        "{} in {}",
+                        source,
+                        path.display()
+                    );
+                }
+            }
+            count += 1;
+        }
+        assert!(count > 1);
+    }
+
+    #[test]
+    fn test_parameter_names_with_whitespace() {
+        let wt = fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/app-spacing.wiki"
+        ))
+        .unwrap();
+        let appearances = parse_appearances(&wt, &ParseOptions::default()).unwrap();
+        let mut names: Vec<_> = appearances.links.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            [
+                "c-characters",
+                "c-creatures",
+                "c-droids",
+                "c-events",
+                "c-locations",
+                "c-organizations",
+                "c-species",
+                "c-technology",
+                "c-vehicles"
+            ]
+        );
+        assert_eq!(appearances.links["c-creatures"][0].name, "Rancor");
+        assert_eq!(appearances.links["c-species"].len(), 2);
+        assert_eq!(
+            appearances.links["c-characters"][0].qualifiers,
+            [Qualifier::FirstAppearance]
+        );
+    }
+
+    #[test]
+    fn test_parameter_names_with_entities() {
+        // The decoded name is shorter than its wikitext and the value starts after multibyte text
+        let wt = "{{App\n|a&lt;éé=\n*[[Cordé]]\n}}";
+        let appearances = parse_appearances(wt, &ParseOptions::default()).unwrap();
+        let corde = &appearances.links["a<éé"][0];
+        assert_eq!(&wt[corde.span.start..corde.span.end], "[[Cordé]]");
+    }
+
+    #[test]
+    fn test_spans_point_into_original_wikitext() {
+        let wt = "{{App\n|c-characters=\n*[[Chian]] {{1st}}\n*[[Cordé]] {{1st}}\n}}";
+        let lines = LineIndex::new(wt);
+        let parsed = parse_nodes(
+            &Configuration::default().parse(wt).nodes,
+            wt,
+            &lines,
+            &Constructs::default(),
+            true,
+        );
+        let SimpleNode::Template(SimpleTemplate { parameters, .. }) = &parsed[0] else {
+            panic!("expected template");
+        };
+        let appearances = flatten_appearance_tree(&collect_appearance_tree(&parameters[0].value));
+        let corde = &appearances[1];
+        assert_eq!(&wt[corde.span.start..corde.span.end], "[[Cordé]]");
+        assert_eq!((corde.span.line, corde.span.column), (4, 2));
+        let first = &corde.templates.as_ref().unwrap()[0];
+        assert_eq!(&wt[first.span.start..first.span.end], "{{1st}}");
+        assert_eq!((first.span.line, first.span.column), (4, 12));
+    }
+
+    #[test]
+    fn test_parse_document_keeps_nodes_with_diagnostics() {
+        let wt = "{{App\n|c-characters=\n*[[Chian]] {{1st}}\n*[[Cordé {{1st}}\n}}";
+        let parsed = parse_document(wt, &Constructs::default());
+        assert_eq!(parsed.diagnostics.len(), 1);
+        assert_eq!(
+            parsed.diagnostics[0].kind,
+            diagnostics::DiagnosticKind::InvalidLinkSyntax
+        );
+        assert_eq!(parsed.diagnostics[0].span.line, 4);
+        let SimpleNode::Template(SimpleTemplate { parameters, .. }) = &parsed.nodes[0] else {
+            panic!("expected template");
+        };
+        assert_eq!(
+            flatten_appearance_tree(&collect_appearance_tree(&parameters[0].value))[0].name,
+            "Chian"
+        );
+    }
+
+    #[test]
+    fn test_nested_appearances() {
+        let wt = "
+*[[Clone Wars]] {{1st}}
+**[[Battle of Geonosis]] {{1st}}
+***[[First Battle of Geonosis]]
+**[[Battle of Christophsis]]
+*[[Galactic Civil War]]";
+        let parsed = parse_nodes(
+            &Configuration::default().parse(wt).nodes,
+            wt,
+            &LineIndex::new(wt),
+            &Constructs::default(),
+            true,
+        );
+        let tree = collect_appearance_tree(&parsed);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].appearance.name, "Clone Wars");
+        assert_eq!(tree[0].appearance.templates.as_ref().unwrap().len(), 1);
+        assert_eq!(tree[0].children.len(), 2);
+        let geonosis = &tree[0].children[0];
+        assert_eq!(geonosis.appearance.parent.as_deref(), Some("Clone Wars"));
+        assert_eq!(
+            geonosis.children[0].appearance.parent.as_deref(),
+            Some("Battle of Geonosis")
+        );
+        assert!(tree[1].children.is_empty());
+
+        let names: Vec<_> = flatten_appearance_tree(&tree)
+            .into_iter()
+            .map(|appearance| appearance.name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "Clone Wars",
+                "Battle of Geonosis",
+                "First Battle of Geonosis",
+                "Battle of Christophsis",
+                "Galactic Civil War"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sibling_lists() {
+        for wt in [
+            "{{App\n|c-characters=\n*[[Rex]]\n\n*[[Cody]]\n}}",
+            "{{App\n|c-characters=\n*[[Rex]]\n<!-- Clones -->\n*[[Cody]]\n}}",
+            "{{App\n|c-characters=\n*[[Rex]]\n<!-- Clones --> \n*[[Cody]]\n}}",
+            "{{App\n|c-characters=\n*[[Rex]]\n{{!}}\n*[[Cody]]\n}}",
+        ] {
+            let appearances = parse_appearances(wt, &ParseOptions::default()).unwrap();
+            let tree = &appearances.tree["c-characters"];
+            assert_eq!(tree.len(), 2, "{wt}");
+            assert_eq!(tree[1].appearance.name, "Cody", "{wt}");
+            assert_eq!(tree[1].appearance.parent, None, "{wt}");
+        }
+    }
+
+    #[test]
+    fn test_leading_qualifiers() {
+        let wt = "{{App\n|c-characters=\n*{{1st}} [[Yoda]] {{C|hologram}}\n*{{Imo}}[[Dooku]]\n}}";
+        let appearances = parse_appearances(wt, &ParseOptions::default()).unwrap();
+        let links = &appearances.links["c-characters"];
+        assert_eq!(
+            links[0].qualifiers,
+            [
+                Qualifier::FirstAppearance,
+                Qualifier::Comment {
+                    text: "hologram".to_string()
+                }
+            ]
+        );
+        assert_eq!(links[1].qualifiers, [Qualifier::IndirectMention]);
+        assert_eq!(links[1].templates.as_ref().unwrap()[0].name, "Imo");
+    }
+
+    #[test]
+    fn test_qualifiers() {
+        let wt = "
+*[[Braata Danlos]] {{1st}} {{C|Statue only}}
+*[[Tox Don]] {{1stID|Tox Don}}
+*[[Battle of Geonosis]] {{Mo}} {{C|[[link|Linked]] comment}}
+*[[Yoda]] {{Flash}} {{Hologram}} {{Imo}} {{Vision}}
+*[[Dooku]] {{Unknown|param}}";
+        let parsed = parse_nodes(
+            &Configuration::default().parse(wt).nodes,
+            wt,
+            &LineIndex::new(wt),
+            &Constructs::default(),
+            true,
+        );
+        let appearances = flatten_appearance_tree(&collect_appearance_tree(&parsed));
+        assert_eq!(
+            appearances[0].qualifiers,
+            [
+                Qualifier::FirstAppearance,
+                Qualifier::Comment {
+                    text: "Statue only".to_string()
+                }
+            ]
+        );
+        assert_eq!(
+            appearances[1].qualifiers,
+            [Qualifier::FirstIdentified {
+                name: Some("Tox Don".to_string())
+            }]
+        );
+        assert_eq!(
+            appearances[2].qualifiers,
+            [
+                Qualifier::MentionedOnly,
+                Qualifier::Comment {
+                    text: "Linked comment".to_string()
+                }
+            ]
+        );
+        assert_eq!(
+            appearances[3].qualifiers,
+            [
+                Qualifier::Flashback,
+                Qualifier::Hologram,
+                Qualifier::IndirectMention,
+                Qualifier::Vision
+            ]
+        );
+        assert!(appearances[4].qualifiers.is_empty());
+        assert_eq!(appearances[4].unknown_templates[0].name, "Unknown");
+        assert_eq!(appearances[4].templates.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_unlinked_appearances() {
+        let wt = "
+*Unidentified clone trooper {{1st}}
+**[[Kamino]]
+*[[CT-411]] \"Ponds\" {{1st}}
+*Unidentified [[Human]] pilot
+*&quot;Ponds&quot;
+*   ";
+        let parsed = parse_nodes(
+            &Configuration::default().parse(wt).nodes,
+            wt,
+            &LineIndex::new(wt),
+            &Constructs::default(),
+            true,
+        );
+        let appearances = flatten_appearance_tree(&collect_appearance_tree(&parsed));
+        let names: Vec<_> = appearances
+            .iter()
+            .map(|appearance| (appearance.kind, appearance.name.as_ref()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (AppearanceKind::Unlinked, "Unidentified clone trooper"),
+                (AppearanceKind::Link, "Kamino"),
+                (AppearanceKind::Link, "CT-411"),
+                (AppearanceKind::Link, "Human"),
+                (AppearanceKind::Unlinked, "\"Ponds\""),
+            ]
+        );
+        assert_eq!(appearances[0].qualifiers, [Qualifier::FirstAppearance]);
+        assert_eq!(
+            &wt[appearances[0].span.start..appearances[0].span.end],
+            "Unidentified clone trooper "
+        );
+        assert_eq!(
+            appearances[1].parent.as_deref(),
+            Some("Unidentified clone trooper")
+        );
+        assert_eq!(
+            &wt[appearances[4].span.start..appearances[4].span.end],
+            "&quot;Ponds&quot;"
+        );
+    }
+
+    #[test]
+    fn test_piped_and_anchored_links() {
+        let wt = "
+*[[1138 (Geonosis)|1138]]
+*[[Coruscant#Senate District|Senate District]]
+*[[Coruscant/Legends]]
+*[[Coruscant]]";
+        let parsed = parse_nodes(
+            &Configuration::default().parse(wt).nodes,
+            wt,
+            &LineIndex::new(wt),
+            &Constructs::default(),
+            true,
+        );
+        let appearances = flatten_appearance_tree(&collect_appearance_tree(&parsed));
+        assert_eq!(appearances[0].title, "1138 (Geonosis)");
+        assert_eq!(appearances[0].text, "1138");
+        assert_eq!(appearances[0].anchor, None);
+        assert_eq!(appearances[1].name, "Coruscant#Senate District");
+        assert_eq!(appearances[1].title, "Coruscant");
+        assert_eq!(appearances[1].anchor.as_deref(), Some("Senate District"));
+        assert_eq!(appearances[1].text, "Senate District");
+        assert_eq!(appearances[2].title, "Coruscant");
+        assert!(appearances[2].legends);
+        assert!(!appearances[3].legends);
+        assert_eq!(appearances[3].text, "Coruscant");
+    }
+
+    #[test]
+    fn test_normalized_titles() {
+        let wt = "
+*[[battle_of Endor]] {{template:1st}}
+*[[Battle of Endor/Legends#Space_battle|Space battle]]";
+        let parsed = parse_nodes(
+            &Configuration::default().parse(wt).nodes,
+            wt,
+            &LineIndex::new(wt),
+            &Constructs::default(),
+            true,
+        );
+        let appearances = flatten_appearance_tree(&collect_appearance_tree(&parsed));
+        assert_eq!(appearances[0].name, "Battle of Endor");
+        assert_eq!(appearances[0].title, "Battle of Endor");
+        assert_eq!(appearances[0].qualifiers, [Qualifier::FirstAppearance]);
+        assert_eq!(appearances[0].templates.as_ref().unwrap()[0].name, "1st");
+        assert_eq!(appearances[1].name, "Battle of Endor/Legends#Space battle");
+        assert_eq!(appearances[1].title, "Battle of Endor");
+        assert_eq!(appearances[1].anchor.as_deref(), Some("Space battle"));
+        assert!(appearances[1].legends);
+    }
+
+    #[test]
+    fn test_1stid() {
+        let wt = "{{App
+|c-characters=
+*[[1138 (Geonosis)|1138]] {{1st}}
+*[[Chian]] {{1st}}
+*[[COO-2180]] {{1st}}
+*[[Cordé]] {{1st}}
+*[[CT-411]] \"Ponds\" {{1st}}
+*[[Theomet Danlé]] {{1st}}
+*[[Braata Danlos]] {{1st}} {{C|Statue only}}
+*[[Oakie Dokes]] {{1st}}
+*[[Lexi Dio]] {{1st}}
+*[[Tox Don]] {{1stID|Tox Don}}
+|c-events=
+*[[Clone Wars]] {{1st}}
+**[[Battle of Geonosis]] {{1st}} {{C|[[link]]}}
+}}";
+        let appearances = parse_appearances(wt, &ParseOptions::default()).unwrap();
+        let qualifiers = |category: &str, name: &str| {
+            appearances.links[category]
+                .iter()
+                .find(|link| link.name == name)
+                .unwrap()
+                .qualifiers
+                .clone()
+        };
+        assert_eq!(
+            qualifiers("c-characters", "1138 (Geonosis)"),
+            [Qualifier::FirstAppearance]
+        );
+        assert_eq!(
+            qualifiers("c-characters", "Tox Don"),
+            [Qualifier::FirstIdentified {
+                name: Some("Tox Don".to_string())
+            }]
+        );
+        assert_eq!(
+            qualifiers("c-characters", "Braata Danlos"),
+            [
+                Qualifier::FirstAppearance,
+                Qualifier::Comment {
+                    text: "Statue only".to_string()
+                }
+            ]
+        );
+        assert_eq!(
+            qualifiers("c-events", "Battle of Geonosis"),
+            [
+                Qualifier::FirstAppearance,
+                Qualifier::Comment {
+                    text: "link".to_string()
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::appearances::{owned_categories, Appearance};
+
+/// Continuity of an `{{App}}` category, from its `c-` or `l-` prefix.
+#[derive(Serialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum Continuity {
+    Canon,
+    Legends,
+    /// No prefix, so the continuity of the article itself.
+    Unprefixed,
+}
+
+/// Old category names and the names Wookieepedia renamed them to, keyed by the old name.
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(transparent)]
+pub struct CategoryAliases(HashMap<String, String>);
+
+impl Default for CategoryAliases {
+    fn default() -> Self {
+        // Some articles still use `creatures` while Wookieepedia moves them to `organisms`
+        CategoryAliases(HashMap::from([(
+            "creatures".to_string(),
+            "organisms".to_string(),
+        )]))
+    }
+}
+
+impl CategoryAliases {
+    /// Default aliases with `overrides` replacing or adding to them.
+    pub fn with_overrides(overrides: CategoryAliases) -> Self {
+        let mut aliases = Self::default();
+        aliases.0.extend(overrides.0);
+        aliases
+    }
+
+    /// Deserializes overrides, see `with_overrides`.
+    pub fn deserialize_overrides<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        Ok(Self::with_overrides(Self::deserialize(deserializer)?))
+    }
+
+    /// Continuity and canonical name of a category, e.g. `Legends` and `organisms` for `l-creatures`.
+    pub fn resolve(&self, category: &str) -> (Continuity, String) {
+        let (continuity, name) = if let Some(name) = category.strip_prefix("c-") {
+            (Continuity::Canon, name)
+        } else if let Some(name) = category.strip_prefix("l-") {
+            (Continuity::Legends, name)
+        } else {
+            (Continuity::Unprefixed, category)
+        };
+        let name = self.0.get(name).map_or(name, String::as_str);
+        (continuity, name.to_string())
+    }
+}
+
+/// Appearances of each continuity by canonical category name. Categories that are aliases of
+/// each other are merged, in the order they are listed.
+#[derive(Serialize, Debug, Default)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct ContinuityAppearances<'a> {
+    pub canon: HashMap<String, Vec<Appearance<'a>>>,
+    pub legends: HashMap<String, Vec<Appearance<'a>>>,
+    pub unprefixed: HashMap<String, Vec<Appearance<'a>>>,
+}
+
+impl<'a> ContinuityAppearances<'a> {
+    pub fn get_mut(&mut self, continuity: Continuity) -> &mut HashMap<String, Vec<Appearance<'a>>> {
+        match continuity {
+            Continuity::Canon => &mut self.canon,
+            Continuity::Legends => &mut self.legends,
+            Continuity::Unprefixed => &mut self.unprefixed,
+        }
+    }
+
+    pub fn categories_mut(&mut self) -> impl Iterator<Item = &mut Vec<Appearance<'a>>> {
+        self.canon
+            .values_mut()
+            .chain(self.legends.values_mut())
+            .chain(self.unprefixed.values_mut())
+    }
+
+    pub fn into_owned(self) -> ContinuityAppearances<'static> {
+        ContinuityAppearances {
+            canon: owned_categories(self.canon),
+            legends: owned_categories(self.legends),
+            unprefixed: owned_categories(self.unprefixed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let aliases = CategoryAliases::default();
+        assert_eq!(
+            aliases.resolve("l-creatures"),
+            (Continuity::Legends, "organisms".to_string())
+        );
+        assert_eq!(
+            aliases.resolve("c-dramatis personae"),
+            (Continuity::Canon, "dramatis personae".to_string())
+        );
+        assert_eq!(
+            aliases.resolve("droids"),
+            (Continuity::Unprefixed, "droids".to_string())
+        );
+
+        let overrides: CategoryAliases =
+            serde_json::from_str(r#"{ "creatures": "beasts" }"#).unwrap();
+        let aliases = CategoryAliases::with_overrides(overrides);
+        assert_eq!(aliases.resolve("creatures").1, "beasts");
+    }
+}
@@ -0,0 +1,155 @@
+//! Parser configuration for Wookieepedia. `Configuration::default()` is tuned for English Wikipedia,
+//! which makes Fandom specific tags like `<tabber>` show up as warnings.
+
+use std::sync::{Arc, RwLock};
+
+use parse_wiki_text::{Configuration, ConfigurationSource};
+use serde::Deserialize;
+
+/// Configuration used by every parsing function. Built lazily from the Wookieepedia preset
+/// and replaced by `configure`.
+static CONFIGURATION: RwLock<Option<Arc<Configuration>>> = RwLock::new(None);
+
+pub fn current() -> Arc<Configuration> {
+    if let Some(configuration) = CONFIGURATION.read().unwrap().as_ref() {
+        return configuration.clone();
+    }
+    CONFIGURATION
+        .write()
+        .unwrap()
+        .get_or_insert_with(|| Arc::new(WikiConfig::default().to_configuration()))
+        .clone()
+}
+
+pub fn set(config: &WikiConfig) {
+    *CONFIGURATION.write().unwrap() = Some(Arc::new(config.to_configuration()));
+}
+
+/// Owned counterpart of `ConfigurationSource`, so it can be read from JSON and partially overridden.
+/// Fields missing from the JSON keep their Wookieepedia value.
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(default, rename_all = "camelCase")]
+pub struct WikiConfig {
+    pub category_namespaces: Vec<String>,
+    pub extension_tags: Vec<String>,
+    pub file_namespaces: Vec<String>,
+    pub link_trail: String,
+    pub magic_words: Vec<String>,
+    pub protocols: Vec<String>,
+    pub redirect_magic_words: Vec<String>,
+}
+
+impl Default for WikiConfig {
+    fn default() -> Self {
+        Self::wookieepedia()
+    }
+}
+
+impl WikiConfig {
+    pub fn wookieepedia() -> Self {
+        WikiConfig {
+            category_namespaces: strings(&["category"]),
+            extension_tags: strings(&[
+                "activityfeed",
+                "categorytree",
+                "charinsert",
+                "choose",
+                "dpl",
+                "dynamicpagelist",
+                "gallery",
+                "imagemap",
+                "indicator",
+                "infobox",
+                "inputbox",
+                "mainpage-endcolumn",
+                "mainpage-leftcolumn-start",
+                "mainpage-rightcolumn-start",
+                "math",
+                "nowiki",
+                "option",
+                "poem",
+                "poll",
+                "pre",
+                "ref",
+                "references",
+                "section",
+                "source",
+                "syntaxhighlight",
+                "tabber",
+                "tabview",
+                "templatedata",
+                "verbatim",
+                "youtube",
+            ]),
+            file_namespaces: strings(&["file", "image"]),
+            link_trail: "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz".to_string(),
+            magic_words: strings(&[
+                "DISAMBIG",
+                "FORCETOC",
+                "HIDDENCAT",
+                "INDEX",
+                "NEWSECTIONLINK",
+                "NOEDITSECTION",
+                "NOGALLERY",
+                "NOINDEX",
+                "NONEWSECTIONLINK",
+                "NOTOC",
+                "NOWYSIWYG",
+                "STATICREDIRECT",
+                "TOC",
+            ]),
+            protocols: strings(&[
+                "//", "ftp://", "ftps://", "git://", "http://", "https://", "irc://", "ircs://",
+                "mailto:", "news:", "sftp://", "ssh://", "svn://", "tel:", "urn:",
+            ]),
+            redirect_magic_words: strings(&["REDIRECT"]),
+        }
+    }
+
+    pub fn to_configuration(&self) -> Configuration {
+        Configuration::new(&ConfigurationSource {
+            category_namespaces: &str_refs(&self.category_namespaces),
+            extension_tags: &str_refs(&self.extension_tags),
+            file_namespaces: &str_refs(&self.file_namespaces),
+            link_trail: &self.link_trail,
+            magic_words: &str_refs(&self.magic_words),
+            protocols: &str_refs(&self.protocols),
+            redirect_magic_words: &str_refs(&self.redirect_magic_words),
+        })
+    }
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn str_refs(values: &[String]) -> Vec<&str> {
+    values.iter().map(String::as_str).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fandom_tags_are_not_warnings() {
+        let wt = "<tabber>\nNovel=[[Dark Disciple]]\n</tabber>";
+        assert!(!Configuration::default().parse(wt).warnings.is_empty());
+        assert!(WikiConfig::wookieepedia()
+            .to_configuration()
+            .parse(wt)
+            .warnings
+            .is_empty());
+    }
+
+    #[test]
+    fn test_partial_override() {
+        let config: WikiConfig = serde_json::from_str(r#"{ "extensionTags": ["ref"] }"#).unwrap();
+        assert_eq!(config.extension_tags, ["ref"]);
+        assert_eq!(
+            config.file_namespaces,
+            WikiConfig::wookieepedia().file_namespaces
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use serde::Deserialize;
+
+/// Which wikitext constructs `parse_nodes` keeps beyond links, lists, templates and text.
+/// Constructs that aren't wanted are dropped, which keeps e.g. appearance lists free of formatting.
+#[derive(Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(default, rename_all = "camelCase")]
+pub struct Constructs {
+    /// Bold and italic toggles.
+    pub formatting: bool,
+    pub external_links: bool,
+    pub images: bool,
+    pub categories: bool,
+    pub headings: bool,
+    pub definition_lists: bool,
+    /// Tags like `<ref>`, and unpaired start and end tags.
+    pub tags: bool,
+    pub tables: bool,
+    pub preformatted: bool,
+    /// Template parameters like `{{{1}}}`.
+    pub parameters: bool,
+    pub magic_words: bool,
+    pub redirects: bool,
+    /// Comments are otherwise removed before parsing, along with the line they stand on.
+    pub comments: bool,
+    /// Paragraph breaks and horizontal dividers.
+    pub breaks: bool,
+}
+
+impl Constructs {
+    /// Everything, for parsing whole articles.
+    pub fn all() -> Self {
+        Constructs {
+            formatting: true,
+            external_links: true,
+            images: true,
+            categories: true,
+            headings: true,
+            definition_lists: true,
+            tags: true,
+            tables: true,
+            preformatted: true,
+            parameters: true,
+            magic_words: true,
+            redirects: true,
+            comments: true,
+            breaks: true,
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt;
+
+use parse_wiki_text::{Warning, WarningMessage};
+use serde::Serialize;
+
+use crate::span::{LineIndex, Span};
+
+/// How many bytes of wikitext around a warning are included in its context.
+const CONTEXT_RADIUS: usize = 30;
+
+/// Kind of problem reported by `parse_wiki_text`.
+#[derive(Serialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub enum DiagnosticKind {
+    DefinitionTermContinuation,
+    EndTagInComment,
+    InvalidCharacter,
+    InvalidHeadingSyntaxRewinding,
+    InvalidLinkSyntax,
+    InvalidParameterSyntax,
+    InvalidTagSyntax,
+    MissingEndTagRewinding,
+    RepeatedEmptyLine,
+    StrayTextInTable,
+    TextAfterRedirect,
+    UnexpectedEndTagRewinding,
+    UnexpectedEndTag,
+    UnexpectedHeadingLevelCorrecting,
+    UnrecognizedTagName,
+    UselessTextInParameter,
+    UselessTextInRedirect,
+}
+
+impl From<WarningMessage> for DiagnosticKind {
+    fn from(message: WarningMessage) -> Self {
+        match message {
+            WarningMessage::DefinitionTermContinuation => {
+                DiagnosticKind::DefinitionTermContinuation
+            }
+            WarningMessage::EndTagInComment => DiagnosticKind::EndTagInComment,
+            WarningMessage::InvalidCharacter => DiagnosticKind::InvalidCharacter,
+            WarningMessage::InvalidHeadingSyntaxRewinding => {
+                DiagnosticKind::InvalidHeadingSyntaxRewinding
+            }
+            WarningMessage::InvalidLinkSyntax => DiagnosticKind::InvalidLinkSyntax,
+            WarningMessage::InvalidParameterSyntax => DiagnosticKind::InvalidParameterSyntax,
+            WarningMessage::InvalidTagSyntax => DiagnosticKind::InvalidTagSyntax,
+            WarningMessage::MissingEndTagRewinding => DiagnosticKind::MissingEndTagRewinding,
+            WarningMessage::RepeatedEmptyLine => DiagnosticKind::RepeatedEmptyLine,
+            WarningMessage::StrayTextInTable => DiagnosticKind::StrayTextInTable,
+            WarningMessage::TextAfterRedirect => DiagnosticKind::TextAfterRedirect,
+            WarningMessage::UnexpectedEndTagRewinding => DiagnosticKind::UnexpectedEndTagRewinding,
+            WarningMessage::UnexpectedEndTag => DiagnosticKind::UnexpectedEndTag,
+            WarningMessage::UnexpectedHeadingLevelCorrecting => {
+                DiagnosticKind::UnexpectedHeadingLevelCorrecting
+            }
+            WarningMessage::UnrecognizedTagName => DiagnosticKind::UnrecognizedTagName,
+            WarningMessage::UselessTextInParameter => DiagnosticKind::UselessTextInParameter,
+            WarningMessage::UselessTextInRedirect => DiagnosticKind::UselessTextInRedirect,
+        }
+    }
+}
+
+/// A non-fatal problem found while parsing, with the wikitext it refers to.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub message: &'static str,
+    pub span: Span,
+    /// The offending wikitext.
+    pub snippet: String,
+    /// The offending wikitext with up to `CONTEXT_RADIUS` bytes on each side.
+    pub context: String,
+}
+
+impl Diagnostic {
+    /// `warning` was found at byte `offset` of the wikitext `lines` indexes, or of its
+    /// preprocessed version. The snippet and context are taken from the original wikitext.
+    pub fn new(warning: &Warning, offset: usize, lines: &LineIndex) -> Self {
+        let span = lines.span(offset + warning.start, offset + warning.end);
+        let wikitext = lines.text();
+        let start = floor_char_boundary(wikitext, span.start);
+        let end = ceil_char_boundary(wikitext, span.end);
+        let context_start = floor_char_boundary(wikitext, start.saturating_sub(CONTEXT_RADIUS));
+        let context_end = ceil_char_boundary(wikitext, end + CONTEXT_RADIUS);
+        Diagnostic {
+            kind: warning.message.into(),
+            message: warning.message.message(),
+            span,
+            snippet: wikitext[start..end].to_string(),
+            context: wikitext[context_start..context_end].to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (line {}, column {})\nErroneous wikitext: {:?}\nContext: {:?}",
+            self.message, self.span.line, self.span.column, self.snippet, self.context
+        )
+    }
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use parse_wiki_text::Configuration;
+
+    use super::*;
+
+    #[test]
+    fn test_warning_at_start_of_input() {
+        let wt = "[[broken link";
+        let lines = LineIndex::new(wt);
+        let warnings = Configuration::default().parse(wt).warnings;
+        let diagnostic = Diagnostic::new(&warnings[0], 0, &lines);
+        assert_eq!(diagnostic.kind, DiagnosticKind::InvalidLinkSyntax);
+        assert_eq!(diagnostic.span.start, 0);
+        assert_eq!(diagnostic.context, wt);
+    }
+
+    #[test]
+    fn test_context_respects_char_boundaries() {
+        let wt = "ééééééééééééééééééééé'''";
+        assert_eq!(floor_char_boundary(wt, 3), 2);
+        assert_eq!(ceil_char_boundary(wt, 3), 4);
+        assert_eq!(ceil_char_boundary(wt, 100), wt.len());
+    }
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+
+use crate::diagnostics::Diagnostic;
+use crate::timeline::HEADER;
+
+/// Why a wikitext couldn't be turned into the requested structure.
+#[derive(Debug)]
+pub enum Error {
+    /// Problems found in strict mode, where any diagnostic fails the parse.
+    Diagnostics(Vec<Diagnostic>),
+    /// Appearances were requested, but the wikitext doesn't start with a template.
+    NotATemplate,
+    /// The wikitext starts with a template that isn't one of the accepted appearances templates.
+    UnexpectedTemplate(String),
+    /// The appearances template has a parameter without a category name.
+    UnnamedParameter,
+    TimelineNotFound,
+    /// Timeline row on `line` with `cells` cells instead of one per column.
+    TimelineRow {
+        line: usize,
+        cells: usize,
+    },
+    /// A query selector that isn't a list of `{{Template|parameter}}` and `[[Link]]` steps.
+    InvalidSelector(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Diagnostics(diagnostics) => {
+                write!(f, "Parsing warnings:")?;
+                for diagnostic in diagnostics {
+                    write!(f, "\n{diagnostic}")?;
+                }
+                Ok(())
+            }
+            Error::NotATemplate => write!(f, "Incorrect input. Template node was expected."),
+            Error::UnexpectedTemplate(name) => write!(
+                f,
+                "Incorrect input. An appearances template was expected, found {{{{{name}}}}}."
+            ),
+            Error::UnnamedParameter => write!(
+                f,
+                "Incorrect input. Template parameter name was expected. (App had an unnamed parameter)"
+            ),
+            Error::TimelineNotFound => write!(
+                f,
+                "Timeline table not found. A table with a Year, Title and Released header was expected."
+            ),
+            Error::TimelineRow { line, cells } => write!(
+                f,
+                "Timeline table rows should have exactly {} cells, line {line} has {cells}",
+                HEADER.len()
+            ),
+            Error::InvalidSelector(reason) => write!(f, "Invalid selector. {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
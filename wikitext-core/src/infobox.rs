@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::text::{render_text, TextRules};
+use crate::{SimpleNode, SimpleTemplate, Span};
+
+/// Entry of the infobox field table, `infoboxFields` on the JS side.
+/// Either a parameter name, or aliases tried in order until one of them has a value.
+#[derive(Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(untagged)]
+pub enum FieldSpec {
+    Name(String),
+    Aliases {
+        name: Option<String>,
+        aliases: Vec<String>,
+        /// Rich text fields are stored under `<key>Details`.
+        #[serde(default)]
+        details: bool,
+    },
+}
+
+impl FieldSpec {
+    fn aliases(&self) -> Vec<&str> {
+        match self {
+            FieldSpec::Name(name) => vec![name],
+            FieldSpec::Aliases { aliases, .. } => aliases.iter().map(String::as_str).collect(),
+        }
+    }
+
+    /// Key the field is returned under, e.g. `releaseDateDetails`.
+    fn key(&self) -> String {
+        match self {
+            FieldSpec::Name(name) => to_camel_case(name),
+            FieldSpec::Aliases {
+                name,
+                aliases,
+                details,
+            } => {
+                let base = name
+                    .as_deref()
+                    .or(aliases.first().map(String::as_str))
+                    .unwrap_or_default();
+                let key = to_camel_case(base);
+                if *details {
+                    key + "Details"
+                } else {
+                    key
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct InfoboxValue<'a> {
+    /// Infobox parameter the value was taken from.
+    pub alias: String,
+    pub text: String,
+    /// Text of each item, when the value is a bulleted list.
+    pub items: Option<Vec<String>>,
+    /// Normalized targets of all links in the value.
+    pub links: Vec<String>,
+    pub nodes: Vec<SimpleNode<'a>>,
+    pub span: Span,
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct Infobox<'a> {
+    pub template: String,
+    pub fields: HashMap<String, InfoboxValue<'a>>,
+    pub span: Span,
+}
+
+/// The infobox is the first top-level template with a named `title` or `image` parameter.
+pub fn find_infobox<'a, 'b>(nodes: &'a [SimpleNode<'b>]) -> Option<&'a SimpleTemplate<'b>> {
+    nodes.iter().find_map(|node| match node {
+        SimpleNode::Template(template)
+            if template.parameters.iter().any(|param| {
+                param.name.as_deref().is_some_and(|name| {
+                    ["title", "image"].contains(&normalize_param_name(name).as_str())
+                })
+            }) =>
+        {
+            Some(template)
+        }
+        _ => None,
+    })
+}
+
+pub fn extract_infobox<'a>(
+    template: &SimpleTemplate<'a>,
+    fields: &[FieldSpec],
+    rules: &TextRules,
+) -> Infobox<'a> {
+    let mut values = HashMap::new();
+    for field in fields {
+        let value = field.aliases().into_iter().find_map(|alias| {
+            let alias = normalize_param_name(alias);
+            let param = template.parameters.iter().find(|param| {
+                param
+                    .name
+                    .as_deref()
+                    .is_some_and(|name| normalize_param_name(name) == alias)
+            })?;
+            let value = infobox_value(&alias, &param.value, param.span, rules);
+            (!value.text.is_empty() || !value.links.is_empty()).then_some(value)
+        });
+        if let Some(value) = value {
+            values.insert(field.key(), value);
+        }
+    }
+    Infobox {
+        template: template.name.to_string(),
+        fields: values,
+        span: template.span,
+    }
+}
+
+fn infobox_value<'a>(
+    alias: &str,
+    nodes: &[SimpleNode<'a>],
+    span: Span,
+    rules: &TextRules,
+) -> InfoboxValue<'a> {
+    let mut content = nodes
+        .iter()
+        .filter(|node| !matches!(node, SimpleNode::Text { value, .. } if value.trim().is_empty()));
+    let items = match (content.next(), content.next()) {
+        (Some(SimpleNode::List { items, .. }), None) => Some(
+            items
+                .iter()
+                .map(|item| render_text(item, rules).trim().to_string())
+                .collect::<Vec<_>>(),
+        ),
+        _ => None,
+    };
+    let text = match &items {
+        Some(items) => items.join("\n"),
+        None => render_text(nodes, rules).trim().to_string(),
+    };
+    let mut links = Vec::new();
+    collect_link_targets(nodes, &mut links);
+    InfoboxValue {
+        alias: alias.to_string(),
+        text,
+        items,
+        links,
+        nodes: nodes.to_vec(),
+        span,
+    }
+}
+
+fn collect_link_targets(nodes: &[SimpleNode], links: &mut Vec<String>) {
+    for node in nodes {
+        match node {
+            SimpleNode::Link { target, .. } => links.push(target.to_string()),
+            SimpleNode::List { items, .. } => {
+                for item in items {
+                    collect_link_targets(item, links);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn normalize_param_name(name: &str) -> String {
+    name.trim().replace('_', " ").to_lowercase()
+}
+
+/// Same as `toCamelCase` in `src/util.ts`, e.g. `release date` becomes `releaseDate`.
+fn to_camel_case(text: &str) -> String {
+    let mut camel = String::new();
+    let mut previous: Option<char> = None;
+    for c in text.chars() {
+        let word_start = !previous.is_some_and(|p| p.is_alphanumeric() || p == '_');
+        if previous.is_none() {
+            camel.extend(c.to_lowercase());
+        } else if word_start || c.is_uppercase() {
+            camel.extend(c.to_uppercase());
+        } else {
+            camel.push(c);
+        }
+        previous = Some(c);
+    }
+    camel.retain(|c| !c.is_whitespace());
+    camel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constructs::Constructs;
+    use crate::parse_document;
+
+    fn fields() -> Vec<FieldSpec> {
+        vec![
+            FieldSpec::Aliases {
+                name: None,
+                aliases: vec!["release date".to_string(), "airdate".to_string()],
+                details: true,
+            },
+            FieldSpec::Name("author".to_string()),
+            FieldSpec::Aliases {
+                name: Some("dateDetails".to_string()),
+                aliases: vec!["timeline".to_string()],
+                details: false,
+            },
+            FieldSpec::Name("media type".to_string()),
+            FieldSpec::Name("isbn".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_extract_infobox() {
+        let wt = "{{Top|can}}
+{{Book
+|title=''Dark Disciple''
+|image=[[File:Dark Disciple.jpg]]
+|author=[[Christie Golden]]
+|release date=
+|airdate=July 7, 2015
+|Timeline=[[19 BBY]]
+|media type=
+*[[Hardcover]]
+*[[E-book]]
+}}
+'''''Dark Disciple''''' is a novel.";
+        let parsed = parse_document(wt, &Constructs::all());
+        let template = find_infobox(&parsed.nodes).unwrap();
+        let infobox = extract_infobox(template, &fields(), &TextRules::default());
+        assert_eq!(infobox.template, "Book");
+        assert_eq!(infobox.fields["author"].text, "Christie Golden");
+        assert_eq!(infobox.fields["author"].links, ["Christie Golden"]);
+        // An empty alias falls through to the next one
+        assert_eq!(infobox.fields["releaseDateDetails"].alias, "airdate");
+        assert_eq!(infobox.fields["releaseDateDetails"].text, "July 7, 2015");
+        assert_eq!(infobox.fields["dateDetails"].text, "19 BBY");
+        assert_eq!(
+            infobox.fields["mediaType"].items.as_deref(),
+            Some(&["Hardcover".to_string(), "E-book".to_string()][..])
+        );
+        assert!(!infobox.fields.contains_key("isbn"));
+    }
+
+    #[test]
+    fn test_to_camel_case() {
+        assert_eq!(to_camel_case("release date"), "releaseDate");
+        assert_eq!(to_camel_case("dateDetails"), "dateDetails");
+        assert_eq!(to_camel_case("Cover artist"), "coverArtist");
+    }
+}
@@ -0,0 +1,1031 @@
+//! Wikitext parsing shared by the native Node module and the Rust rewrite: the simplified node
+//! tree, appearances, timeline and infobox extraction, title normalization and the parser
+//! configuration. Independent of Node, so usable from plain Rust.
+
+use std::borrow::Cow;
+
+use parse_wiki_text::{
+    Configuration, DefinitionListItemType, ListItem, Node, Parameter, Positioned, TableCellType,
+    TableRow,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use constructs::Constructs;
+use diagnostics::Diagnostic;
+use options::ParseOptions;
+use preprocess::{preprocess, restore};
+use span::{LineIndex, Span};
+use tagged::{NodeFormat, TaggedNode};
+use title::{normalize_link_target, normalize_template_name};
+
+pub use error::{Error, Result};
+
+pub mod appearances;
+pub mod categories;
+pub mod configuration;
+pub mod constructs;
+pub mod diagnostics;
+mod error;
+pub mod infobox;
+pub mod options;
+pub mod preprocess;
+pub mod qualifiers;
+pub mod query;
+pub mod sections;
+pub mod serialize;
+pub mod span;
+pub mod tagged;
+pub mod text;
+pub mod timeline;
+pub mod title;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct SimpleTemplate<'a> {
+    pub name: Cow<'a, str>,
+    pub parameters: Vec<SimpleParameter<'a>>,
+    pub span: Span,
+}
+
+/// Serialized externally tagged, or internally tagged within `tagged::with_node_format`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(remote = "Self", rename_all_fields = "camelCase")]
+pub enum SimpleNode<'a> {
+    /// `*` list, or `#` list when `ordered`.
+    List {
+        ordered: bool,
+        items: Vec<Vec<SimpleNode<'a>>>,
+        span: Span,
+    },
+    DefinitionList {
+        items: Vec<SimpleDefinitionItem<'a>>,
+        span: Span,
+    },
+    Template(SimpleTemplate<'a>),
+    Link {
+        target: Cow<'a, str>,
+        text: Cow<'a, str>,
+        span: Span,
+    },
+    ExternalLink {
+        url: Cow<'a, str>,
+        text: Vec<SimpleNode<'a>>,
+        span: Span,
+    },
+    Image {
+        target: Cow<'a, str>,
+        text: Vec<SimpleNode<'a>>,
+        span: Span,
+    },
+    Category {
+        target: Cow<'a, str>,
+        sort_key: Option<Cow<'a, str>>,
+        span: Span,
+    },
+    Text {
+        value: Cow<'a, str>,
+        span: Span,
+    },
+    /// `'''` toggle. Formatting isn't nested, the text up to the next toggle is bold.
+    Bold {
+        span: Span,
+    },
+    /// `''` toggle.
+    Italic {
+        span: Span,
+    },
+    /// `'''''` toggle.
+    BoldItalic {
+        span: Span,
+    },
+    Heading {
+        level: u8,
+        nodes: Vec<SimpleNode<'a>>,
+        span: Span,
+    },
+    /// Paired tag, e.g. `<ref>` or `<span>`. `attributes` are the wikitext after the name,
+    /// e.g. `name="x"`.
+    Tag {
+        name: Cow<'a, str>,
+        attributes: Cow<'a, str>,
+        nodes: Vec<SimpleNode<'a>>,
+        span: Span,
+    },
+    StartTag {
+        name: Cow<'a, str>,
+        attributes: Cow<'a, str>,
+        span: Span,
+    },
+    EndTag {
+        name: Cow<'a, str>,
+        span: Span,
+    },
+    Table(SimpleTable<'a>),
+    Preformatted {
+        nodes: Vec<SimpleNode<'a>>,
+        span: Span,
+    },
+    /// `{{{name|default}}}`
+    Parameter {
+        name: Cow<'a, str>,
+        default: Option<Vec<SimpleNode<'a>>>,
+        span: Span,
+    },
+    /// E.g. `__NOTOC__`, named without the underscores.
+    MagicWord {
+        name: Cow<'a, str>,
+        span: Span,
+    },
+    Redirect {
+        target: Cow<'a, str>,
+        span: Span,
+    },
+    Comment {
+        text: Cow<'a, str>,
+        span: Span,
+    },
+    HorizontalDivider {
+        span: Span,
+    },
+    ParagraphBreak {
+        span: Span,
+    },
+}
+
+impl Serialize for SimpleNode<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match tagged::node_format() {
+            NodeFormat::External => SimpleNode::serialize(self, serializer),
+            NodeFormat::Tagged => TaggedNode::from(self).serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SimpleNode<'_> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        SimpleNode::deserialize(deserializer)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct SimpleDefinitionItem<'a> {
+    /// `;` term, otherwise `:` details.
+    pub term: bool,
+    pub nodes: Vec<SimpleNode<'a>>,
+    pub span: Span,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct SimpleTable<'a> {
+    pub attributes: Cow<'a, str>,
+    pub captions: Vec<Vec<SimpleNode<'a>>>,
+    pub rows: Vec<SimpleTableRow<'a>>,
+    pub span: Span,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct SimpleTableRow<'a> {
+    pub attributes: Cow<'a, str>,
+    pub cells: Vec<SimpleTableCell<'a>>,
+    pub span: Span,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct SimpleTableCell<'a> {
+    /// `!` cell, otherwise `|`.
+    pub heading: bool,
+    pub attributes: Option<Cow<'a, str>>,
+    pub content: Vec<SimpleNode<'a>>,
+    pub span: Span,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct SimpleParameter<'a> {
+    pub name: Option<Cow<'a, str>>,
+    pub value: Vec<SimpleNode<'a>>,
+    pub span: Span,
+}
+
+impl SimpleNode<'_> {
+    /// Copy of the node that doesn't borrow from the wikitext it was parsed from.
+    pub fn into_owned(self) -> SimpleNode<'static> {
+        match self {
+            SimpleNode::List {
+                ordered,
+                items,
+                span,
+            } => SimpleNode::List {
+                ordered,
+                items: items.into_iter().map(owned_nodes).collect(),
+                span,
+            },
+            SimpleNode::DefinitionList { items, span } => SimpleNode::DefinitionList {
+                items: items
+                    .into_iter()
+                    .map(SimpleDefinitionItem::into_owned)
+                    .collect(),
+                span,
+            },
+            SimpleNode::Template(template) => SimpleNode::Template(template.into_owned()),
+            SimpleNode::Link { target, text, span } => SimpleNode::Link {
+                target: owned(target),
+                text: owned(text),
+                span,
+            },
+            SimpleNode::ExternalLink { url, text, span } => SimpleNode::ExternalLink {
+                url: owned(url),
+                text: owned_nodes(text),
+                span,
+            },
+            SimpleNode::Image { target, text, span } => SimpleNode::Image {
+                target: owned(target),
+                text: owned_nodes(text),
+                span,
+            },
+            SimpleNode::Category {
+                target,
+                sort_key,
+                span,
+            } => SimpleNode::Category {
+                target: owned(target),
+                sort_key: sort_key.map(owned),
+                span,
+            },
+            SimpleNode::Text { value, span } => SimpleNode::Text {
+                value: owned(value),
+                span,
+            },
+            SimpleNode::Bold { span } => SimpleNode::Bold { span },
+            SimpleNode::Italic { span } => SimpleNode::Italic { span },
+            SimpleNode::BoldItalic { span } => SimpleNode::BoldItalic { span },
+            SimpleNode::Heading { level, nodes, span } => SimpleNode::Heading {
+                level,
+                nodes: owned_nodes(nodes),
+                span,
+            },
+            SimpleNode::Tag {
+                name,
+                attributes,
+                nodes,
+                span,
+            } => SimpleNode::Tag {
+                name: owned(name),
+                attributes: owned(attributes),
+                nodes: owned_nodes(nodes),
+                span,
+            },
+            SimpleNode::StartTag {
+                name,
+                attributes,
+                span,
+            } => SimpleNode::StartTag {
+                name: owned(name),
+                attributes: owned(attributes),
+                span,
+            },
+            SimpleNode::EndTag { name, span } => SimpleNode::EndTag {
+                name: owned(name),
+                span,
+            },
+            SimpleNode::Table(table) => SimpleNode::Table(table.into_owned()),
+            SimpleNode::Preformatted { nodes, span } => SimpleNode::Preformatted {
+                nodes: owned_nodes(nodes),
+                span,
+            },
+            SimpleNode::Parameter {
+                name,
+                default,
+                span,
+            } => SimpleNode::Parameter {
+                name: owned(name),
+                default: default.map(owned_nodes),
+                span,
+            },
+            SimpleNode::MagicWord { name, span } => SimpleNode::MagicWord {
+                name: owned(name),
+                span,
+            },
+            SimpleNode::Redirect { target, span } => SimpleNode::Redirect {
+                target: owned(target),
+                span,
+            },
+            SimpleNode::Comment { text, span } => SimpleNode::Comment {
+                text: owned(text),
+                span,
+            },
+            SimpleNode::HorizontalDivider { span } => SimpleNode::HorizontalDivider { span },
+            SimpleNode::ParagraphBreak { span } => SimpleNode::ParagraphBreak { span },
+        }
+    }
+}
+
+impl SimpleTemplate<'_> {
+    pub fn into_owned(self) -> SimpleTemplate<'static> {
+        SimpleTemplate {
+            name: owned(self.name),
+            parameters: self
+                .parameters
+                .into_iter()
+                .map(SimpleParameter::into_owned)
+                .collect(),
+            span: self.span,
+        }
+    }
+}
+
+impl SimpleDefinitionItem<'_> {
+    pub fn into_owned(self) -> SimpleDefinitionItem<'static> {
+        SimpleDefinitionItem {
+            term: self.term,
+            nodes: owned_nodes(self.nodes),
+            span: self.span,
+        }
+    }
+}
+
+impl SimpleTable<'_> {
+    pub fn into_owned(self) -> SimpleTable<'static> {
+        SimpleTable {
+            attributes: owned(self.attributes),
+            captions: self.captions.into_iter().map(owned_nodes).collect(),
+            rows: self
+                .rows
+                .into_iter()
+                .map(|row| SimpleTableRow {
+                    attributes: owned(row.attributes),
+                    cells: row
+                        .cells
+                        .into_iter()
+                        .map(|cell| SimpleTableCell {
+                            heading: cell.heading,
+                            attributes: cell.attributes.map(owned),
+                            content: owned_nodes(cell.content),
+                            span: cell.span,
+                        })
+                        .collect(),
+                    span: row.span,
+                })
+                .collect(),
+            span: self.span,
+        }
+    }
+}
+
+impl SimpleParameter<'_> {
+    pub fn into_owned(self) -> SimpleParameter<'static> {
+        SimpleParameter {
+            name: self.name.map(owned),
+            value: owned_nodes(self.value),
+            span: self.span,
+        }
+    }
+}
+
+fn owned(text: Cow<str>) -> Cow<'static, str> {
+    Cow::Owned(text.into_owned())
+}
+
+pub fn owned_nodes(nodes: Vec<SimpleNode>) -> Vec<SimpleNode<'static>> {
+    nodes.into_iter().map(SimpleNode::into_owned).collect()
+}
+
+/// Text of `nodes` as it was parsed, so still with the characters `preprocess` escaped.
+/// A single text node is returned as the slice it was parsed from.
+fn reduce_nodes_to_text<'b>(nodes: &[Node<'b>]) -> Cow<'b, str> {
+    if let [Node::Text { value, .. }] = nodes {
+        return Cow::Borrowed(value);
+    }
+    let mut text = String::new();
+    for node in nodes {
+        match node {
+            Node::Text { value, .. } => text.push_str(value),
+            Node::CharacterEntity { character, .. } => text.push(*character),
+            _ => (),
+        }
+    }
+    Cow::Owned(text)
+}
+
+pub fn simple_nodes_to_text(nodes: &[SimpleNode]) -> String {
+    nodes
+        .iter()
+        .map(|node| match node {
+            SimpleNode::Text { value, .. } => value,
+            SimpleNode::Link { text, .. } => text,
+            _ => "",
+        })
+        .collect()
+}
+
+/// Whether `wikitext` can hold a construct like a list that is only parsed at the start of a line.
+fn has_line_constructs(wikitext: &str) -> bool {
+    wikitext.contains('\n')
+        || wikitext.starts_with(['*', '#', ':', ';', '=', ' ', '\t', '-'])
+        || wikitext.starts_with("{|")
+}
+
+/// Builds the `SimpleNode`s of one parse.
+struct NodeBuilder<'a, 'b> {
+    /// Wikitext that was parsed. The strings of its `parse_wiki_text` nodes are slices of it.
+    parsed: &'b str,
+    /// Index of the original wikitext, which `parsed` may be a preprocessed version of.
+    lines: &'b LineIndex<'a, 'b>,
+    configuration: &'b Configuration,
+    constructs: &'b Constructs,
+    reparse_parameters: bool,
+}
+
+impl<'a> NodeBuilder<'a, '_> {
+    /// `value` with the escaped characters restored. A slice of the parsed wikitext that is
+    /// unchanged in the original is borrowed from the original, instead of copied.
+    fn text(&self, value: &str) -> Cow<'a, str> {
+        let restored = restore(value);
+        let offset = (value.as_ptr() as usize).wrapping_sub(self.parsed.as_ptr() as usize);
+        if offset <= self.parsed.len() {
+            let start = self.lines.to_source(offset);
+            let source = self.lines.text().get(start..start + restored.len());
+            if let Some(source) = source.filter(|source| *source == restored) {
+                return Cow::Borrowed(source);
+            }
+        }
+        Cow::Owned(restored.into_owned())
+    }
+
+    /// Text of `value` normalized, still borrowed when `normalize` doesn't change it.
+    fn normalized(&self, value: &str, normalize: fn(&str) -> Cow<str>) -> Cow<'a, str> {
+        let value = self.text(value);
+        let changed = match normalize(&value) {
+            Cow::Borrowed(normalized) if normalized.len() == value.len() => None,
+            normalized => Some(normalized.into_owned()),
+        };
+        changed.map_or(value, Cow::Owned)
+    }
+
+    /// Wikitext `nodes` were parsed from, e.g. table attributes.
+    fn source_text(&self, nodes: &[Node], wikitext: &str) -> Cow<'a, str> {
+        match (nodes.first(), nodes.last()) {
+            (Some(first), Some(last)) => self.text(wikitext[first.start()..last.end()].trim()),
+            _ => Cow::Borrowed(""),
+        }
+    }
+
+    /// Attributes of the start tag `tag` begins with, which ends at the first `>` as in the parser.
+    fn tag_attributes(&self, tag: &str, name: &str) -> Cow<'a, str> {
+        let end = tag.find('>').unwrap_or(tag.len());
+        let attributes = tag[..end].get(1 + name.len()..).unwrap_or("");
+        self.text(attributes.strip_suffix('/').unwrap_or(attributes).trim())
+    }
+
+    fn list_items(
+        &self,
+        ordered: bool,
+        items: &[ListItem],
+        span: Span,
+        wikitext: &str,
+        offset: usize,
+    ) -> SimpleNode<'a> {
+        SimpleNode::List {
+            ordered,
+            items: items
+                .iter()
+                .map(|item| self.nodes(&item.nodes, wikitext, offset))
+                .collect(),
+            span,
+        }
+    }
+
+    /// Splits the URL off the text of an external link, `[https://starwars.com Official site]`.
+    fn external_link(
+        &self,
+        nodes: &[Node],
+        wikitext: &str,
+        offset: usize,
+    ) -> (Cow<'a, str>, Vec<SimpleNode<'a>>) {
+        let mut text = self.nodes(nodes, wikitext, offset);
+        let (
+            Some(Node::Text {
+                start,
+                end,
+                value: parsed,
+            }),
+            Some(SimpleNode::Text { value, span }),
+        ) = (nodes.first(), text.first_mut())
+        else {
+            return (Cow::Borrowed(""), text);
+        };
+        // Split in the parsed text, whose offsets the span is mapped from
+        let url_end = parsed.find(char::is_whitespace).unwrap_or(parsed.len());
+        let rest_start = parsed.len() - parsed[url_end..].trim_start().len();
+        let url = self.text(&parsed[..url_end]);
+        if rest_start == parsed.len() {
+            text.remove(0);
+        } else {
+            *span = self.lines.span(offset + start + rest_start, offset + end);
+            *value = self.text(&parsed[rest_start..]);
+        }
+        (url, text)
+    }
+
+    fn parameter(&self, param: &Parameter, wikitext: &str, offset: usize) -> SimpleParameter<'a> {
+        // Lists don't get parsed inside templates, so parse the raw wikitext
+        // of the value, which starts after the `=` and whitespace of a name
+        let start = match &param.name {
+            Some(_) => param.value.first().map_or(param.end, Node::start),
+            None => param.start,
+        };
+        let param_wt = &wikitext[start..param.end];
+        let value = if self.reparse_parameters && has_line_constructs(param_wt) {
+            let reparsed = self.configuration.parse(param_wt);
+            self.nodes(&reparsed.nodes, param_wt, offset + start)
+        } else {
+            // It would parse the same on its own
+            self.nodes(&param.value, wikitext, offset)
+        };
+        SimpleParameter {
+            name: param
+                .name
+                .as_ref()
+                .map(|name| self.text(reduce_nodes_to_text(name).trim())),
+            value,
+            span: self.lines.span(offset + param.start, offset + param.end),
+        }
+    }
+
+    fn table_rows(
+        &self,
+        rows: &[TableRow],
+        wikitext: &str,
+        offset: usize,
+    ) -> Vec<SimpleTableRow<'a>> {
+        rows.iter()
+            .map(|row| SimpleTableRow {
+                attributes: self.source_text(&row.attributes, wikitext),
+                cells: row
+                    .cells
+                    .iter()
+                    .map(|cell| SimpleTableCell {
+                        heading: cell.type_ == TableCellType::Heading,
+                        attributes: cell
+                            .attributes
+                            .as_ref()
+                            .map(|attributes| self.source_text(attributes, wikitext)),
+                        content: self.nodes(&cell.content, wikitext, offset),
+                        span: self.lines.span(offset + cell.start, offset + cell.end),
+                    })
+                    .collect(),
+                span: self.lines.span(offset + row.start, offset + row.end),
+            })
+            .collect()
+    }
+
+    /// `wikitext` is the text `nodes` were parsed from, found at byte `offset` of the parsed wikitext.
+    fn nodes(&self, nodes: &[Node], wikitext: &str, offset: usize) -> Vec<SimpleNode<'a>> {
+        let constructs = self.constructs;
+        let mut node_list = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let span = self.lines.span(offset + node.start(), offset + node.end());
+            let children = |nodes: &[Node]| self.nodes(nodes, wikitext, offset);
+            match node {
+                Node::Link { target, text, .. } => {
+                    node_list.push(SimpleNode::Link {
+                        target: self.normalized(target, normalize_link_target),
+                        text: self.text(&reduce_nodes_to_text(text)),
+                        span,
+                    });
+                }
+                Node::UnorderedList { items, .. } => {
+                    node_list.push(self.list_items(false, items, span, wikitext, offset));
+                }
+                Node::OrderedList { items, .. } => {
+                    node_list.push(self.list_items(true, items, span, wikitext, offset));
+                }
+                Node::Text { value, .. } => {
+                    // Text can be left empty by a `<nowiki/>` placeholder alone
+                    let value = self.text(value);
+                    if !value.is_empty() {
+                        node_list.push(SimpleNode::Text { value, span });
+                    }
+                }
+                Node::CharacterEntity { character, .. } => {
+                    node_list.push(SimpleNode::Text {
+                        value: Cow::Owned(character.to_string()),
+                        span,
+                    });
+                }
+                Node::Template {
+                    name, parameters, ..
+                } => {
+                    node_list.push(SimpleNode::Template(SimpleTemplate {
+                        name: self
+                            .normalized(reduce_nodes_to_text(name).trim(), normalize_template_name),
+                        parameters: parameters
+                            .iter()
+                            .map(|param| self.parameter(param, wikitext, offset))
+                            .collect(),
+                        span,
+                    }));
+                }
+                Node::Bold { .. } if constructs.formatting => {
+                    node_list.push(SimpleNode::Bold { span });
+                }
+                Node::Italic { .. } if constructs.formatting => {
+                    node_list.push(SimpleNode::Italic { span });
+                }
+                Node::BoldItalic { .. } if constructs.formatting => {
+                    node_list.push(SimpleNode::BoldItalic { span });
+                }
+                Node::ExternalLink { nodes, .. } if constructs.external_links => {
+                    let (url, text) = self.external_link(nodes, wikitext, offset);
+                    node_list.push(SimpleNode::ExternalLink { url, text, span });
+                }
+                Node::Image { target, text, .. } if constructs.images => {
+                    node_list.push(SimpleNode::Image {
+                        target: self.normalized(target, title::normalize_title),
+                        text: children(text),
+                        span,
+                    });
+                }
+                Node::Category {
+                    target, ordinal, ..
+                } if constructs.categories => {
+                    node_list.push(SimpleNode::Category {
+                        target: self.normalized(target, title::normalize_title),
+                        sort_key: Some(self.text(&reduce_nodes_to_text(ordinal)))
+                            .filter(|key| !key.is_empty()),
+                        span,
+                    });
+                }
+                Node::Heading { level, nodes, .. } if constructs.headings => {
+                    node_list.push(SimpleNode::Heading {
+                        level: *level,
+                        nodes: children(nodes),
+                        span,
+                    });
+                }
+                Node::DefinitionList { items, .. } if constructs.definition_lists => {
+                    node_list.push(SimpleNode::DefinitionList {
+                        items: items
+                            .iter()
+                            .map(|item| SimpleDefinitionItem {
+                                term: item.type_ == DefinitionListItemType::Term,
+                                nodes: children(&item.nodes),
+                                span: self.lines.span(offset + item.start, offset + item.end),
+                            })
+                            .collect(),
+                        span,
+                    });
+                }
+                Node::Tag {
+                    name, nodes, start, ..
+                } if constructs.tags => {
+                    node_list.push(SimpleNode::Tag {
+                        name: self.text(name),
+                        attributes: self.tag_attributes(&wikitext[*start..], name),
+                        nodes: children(nodes),
+                        span,
+                    });
+                }
+                Node::StartTag { name, start, .. } if constructs.tags => {
+                    node_list.push(SimpleNode::StartTag {
+                        name: self.text(name),
+                        attributes: self.tag_attributes(&wikitext[*start..], name),
+                        span,
+                    });
+                }
+                Node::EndTag { name, .. } if constructs.tags => {
+                    node_list.push(SimpleNode::EndTag {
+                        name: self.text(name),
+                        span,
+                    });
+                }
+                Node::Table {
+                    attributes,
+                    captions,
+                    rows,
+                    ..
+                } if constructs.tables => {
+                    node_list.push(SimpleNode::Table(SimpleTable {
+                        attributes: self.source_text(attributes, wikitext),
+                        captions: captions
+                            .iter()
+                            .map(|caption| children(&caption.content))
+                            .collect(),
+                        rows: self.table_rows(rows, wikitext, offset),
+                        span,
+                    }));
+                }
+                Node::Preformatted { nodes, .. } if constructs.preformatted => {
+                    node_list.push(SimpleNode::Preformatted {
+                        nodes: children(nodes),
+                        span,
+                    });
+                }
+                Node::Parameter { name, default, .. } if constructs.parameters => {
+                    node_list.push(SimpleNode::Parameter {
+                        name: self.text(&reduce_nodes_to_text(name)),
+                        default: default.as_deref().map(children),
+                        span,
+                    });
+                }
+                Node::MagicWord { start, end } if constructs.magic_words => {
+                    node_list.push(SimpleNode::MagicWord {
+                        name: self.text(wikitext[*start..*end].trim_matches('_')),
+                        span,
+                    });
+                }
+                Node::Redirect { target, .. } if constructs.redirects => {
+                    node_list.push(SimpleNode::Redirect {
+                        target: self.normalized(target, normalize_link_target),
+                        span,
+                    });
+                }
+                Node::Comment { start, end } if constructs.comments => {
+                    let comment = &wikitext[*start..*end];
+                    let comment = comment.strip_prefix("<!--").unwrap_or(comment);
+                    node_list.push(SimpleNode::Comment {
+                        text: self.text(comment.strip_suffix("-->").unwrap_or(comment)),
+                        span,
+                    });
+                }
+                Node::HorizontalDivider { .. } if constructs.breaks => {
+                    node_list.push(SimpleNode::HorizontalDivider { span });
+                }
+                Node::ParagraphBreak { .. } if constructs.breaks => {
+                    node_list.push(SimpleNode::ParagraphBreak { span });
+                }
+                _ => (),
+            };
+        }
+        node_list
+    }
+}
+
+/// `wikitext` is the text `nodes` were parsed from, and the text `lines` indexes or its preprocessed
+/// version. Text is borrowed from the indexed text where it can be.
+fn parse_nodes<'a>(
+    nodes: &[Node],
+    wikitext: &str,
+    lines: &LineIndex<'a, '_>,
+    constructs: &Constructs,
+    reparse_parameters: bool,
+) -> Vec<SimpleNode<'a>> {
+    let builder = NodeBuilder {
+        parsed: wikitext,
+        lines,
+        configuration: &configuration::current(),
+        constructs,
+        reparse_parameters,
+    };
+    builder.nodes(nodes, wikitext, 0)
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct ParsedWikitext<'a> {
+    pub nodes: Vec<SimpleNode<'a>>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ParsedWikitext<'_> {
+    pub fn into_owned(self) -> ParsedWikitext<'static> {
+        ParsedWikitext {
+            nodes: owned_nodes(self.nodes),
+            diagnostics: self.diagnostics,
+        }
+    }
+}
+
+/// Parses the preprocessed `wikitext`, with spans and diagnostics pointing at the original.
+/// Unless preprocessing changed it, text in the nodes is borrowed from `wikitext`.
+pub fn parse_document<'a>(wikitext: &'a str, constructs: &Constructs) -> ParsedWikitext<'a> {
+    parse_with(wikitext, constructs, true)
+}
+
+fn parse_with<'a>(
+    wikitext: &'a str,
+    constructs: &Constructs,
+    reparse_parameters: bool,
+) -> ParsedWikitext<'a> {
+    let preprocessed = preprocess(wikitext, constructs.comments);
+    let lines = LineIndex::with_source_map(wikitext, &preprocessed.source_map);
+    let result = configuration::current().parse(&preprocessed.text);
+    ParsedWikitext {
+        nodes: parse_nodes(
+            &result.nodes,
+            &preprocessed.text,
+            &lines,
+            constructs,
+            reparse_parameters,
+        ),
+        diagnostics: result
+            .warnings
+            .iter()
+            .map(|warning| Diagnostic::new(warning, 0, &lines))
+            .collect(),
+    }
+}
+
+/// Parses with the constructs and parameter parsing of `options`. In strict mode any diagnostic
+/// is turned into an error, so callers only ever see clean parses.
+pub fn parse_checked<'a>(wikitext: &'a str, options: &ParseOptions) -> Result<ParsedWikitext<'a>> {
+    let parsed = parse_with(wikitext, &options.constructs, options.reparse_parameters);
+    if options.strict && !parsed.diagnostics.is_empty() {
+        return Err(Error::Diagnostics(parsed.diagnostics));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use parse_wiki_text::Configuration;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_list_items() {
+        let wt = "*[[target]]";
+        let lines = LineIndex::new(wt);
+        let doc = Configuration::default().parse(wt);
+        assert!(matches!(&doc.nodes[0], Node::UnorderedList { .. }));
+        let parsed = parse_nodes(&doc.nodes, wt, &lines, &Constructs::default(), true);
+        assert_eq!(
+            parsed,
+            [SimpleNode::List {
+                ordered: false,
+                items: vec![vec![SimpleNode::Link {
+                    target: "Target".into(),
+                    text: "target".into(),
+                    span: lines.span(1, 11),
+                }]],
+                span: lines.span(0, 11),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_list_in_template() {
+        let wt = "{{template_name|param_name=\n*[[link_target]]}}";
+        let lines = LineIndex::new(wt);
+        let doc = Configuration::default().parse(wt);
+        assert!(matches!(&doc.nodes[0], Node::Template { .. }));
+        let parsed = parse_nodes(&doc.nodes, wt, &lines, &Constructs::default(), true);
+        assert_eq!(
+            parsed,
+            vec![SimpleNode::Template(SimpleTemplate {
+                name: "Template name".into(),
+                parameters: vec![SimpleParameter {
+                    name: Some("param_name".into()),
+                    value: vec![SimpleNode::List {
+                        ordered: false,
+                        items: vec![vec![SimpleNode::Link {
+                            target: "Link target".into(),
+                            text: "link_target".into(),
+                            span: lines.span(29, 44),
+                        }]],
+                        span: lines.span(28, 44),
+                    }],
+                    span: lines.span(16, 44),
+                }],
+                span: lines.span(0, 46),
+            })]
+        );
+    }
+
+    #[test]
+    fn test_wookieepedia_tags() {
+        let parsed = parse_document(
+            "{{App\n|c-characters=\n*[[Yoda]]<ref>[[Tales]]</ref>\n}}",
+            &Constructs::default(),
+        );
+        assert!(parsed.diagnostics.is_empty());
+        let parsed = parse_document(
+            "<tabber>\nCanon=[[Yoda]]\n</tabber>",
+            &Constructs::default(),
+        );
+        assert!(parsed.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_all_constructs() {
+        let wt = "==Plot==
+''[[Dark Disciple]]'' is a novel.<ref>[https://starwars.com Official site]</ref>
+{|class=\"wikitable\"
+!Title
+|-
+|[[Yoda]]
+|}";
+        let parsed = parse_document(wt, &Constructs::all());
+        assert!(parsed.diagnostics.is_empty());
+        let nodes = &parsed.nodes;
+        assert!(
+            matches!(&nodes[0], SimpleNode::Heading { level: 2, nodes, .. }
+            if matches!(&nodes[..], [SimpleNode::Text { value, .. }] if value == "Plot"))
+        );
+        assert!(matches!(nodes[1], SimpleNode::Italic { .. }));
+        assert!(matches!(&nodes[2], SimpleNode::Link { target, .. } if target == "Dark Disciple"));
+        let SimpleNode::Tag {
+            name,
+            nodes: ref_nodes,
+            ..
+        } = &nodes[5]
+        else {
+            panic!("expected <ref>, got {:?}", nodes[5]);
+        };
+        assert_eq!(name, "ref");
+        let SimpleNode::ExternalLink { url, text, .. } = &ref_nodes[0] else {
+            panic!("expected an external link, got {:?}", ref_nodes[0]);
+        };
+        assert_eq!(url, "https://starwars.com");
+        assert_eq!(simple_nodes_to_text(text), "Official site");
+        let SimpleNode::Table(table) = nodes.last().unwrap() else {
+            panic!("expected a table, got {:?}", nodes.last());
+        };
+        assert_eq!(table.attributes, "class=\"wikitable\"");
+        assert!(table.rows[0].cells[0].heading);
+        assert!(
+            matches!(&table.rows[1].cells[0].content[0], SimpleNode::Link { target, .. } if target == "Yoda")
+        );
+
+        // Without the flags only the text and links are left
+        let parsed = parse_document(wt, &Constructs::default());
+        assert!(matches!(parsed.nodes[0], SimpleNode::Link { .. }));
+    }
+
+    #[test]
+    fn test_external_link_text_span() {
+        let wt = "<!-- Official sites -->\n[https://starwars.com Official site] and more";
+        let constructs = Constructs {
+            external_links: true,
+            ..Constructs::default()
+        };
+        let nodes = parse_document(wt, &constructs).nodes;
+        let SimpleNode::ExternalLink { url, text, span } = &nodes[0] else {
+            panic!("expected an external link, got {:?}", nodes[0]);
+        };
+        assert_eq!(url, "https://starwars.com");
+        assert_eq!(
+            &wt[span.start..span.end],
+            "[https://starwars.com Official site]"
+        );
+        let SimpleNode::Text { span, .. } = &text[0] else {
+            panic!("expected text, got {:?}", text[0]);
+        };
+        assert_eq!(&wt[span.start..span.end], "Official site");
+    }
+
+    #[test]
+    fn test_nodes_borrow_from_wikitext() {
+        let wt = "{{App|c-characters=[[Yoda]] and [[dark_side|Sith]] &amp; more}}";
+        let parsed = parse_document(wt, &Constructs::default());
+        let SimpleNode::Template(template) = &parsed.nodes[0] else {
+            panic!("expected a template, got {:?}", parsed.nodes[0]);
+        };
+        assert!(matches!(template.name, Cow::Borrowed("App")));
+        let value = &template.parameters[0].value;
+        assert!(matches!(
+            &value[0],
+            SimpleNode::Link {
+                target: Cow::Borrowed("Yoda"),
+                ..
+            }
+        ));
+        // Normalized targets and decoded entities are owned
+        assert!(
+            matches!(&value[2], SimpleNode::Link { target: Cow::Owned(target), text: Cow::Borrowed("Sith"), .. } if target == "Dark side")
+        );
+        assert_eq!(simple_nodes_to_text(value), "Yoda and Sith & more");
+
+        let owned: SimpleNode<'static> = parsed.nodes[0].clone().into_owned();
+        assert_eq!(owned, parsed.nodes[0]);
+    }
+
+    #[test]
+    fn test_placeholder_characters_stay_text() {
+        let wt = "[[A\u{E000}B]]";
+        let parsed = parse_document(wt, &Constructs::default());
+        let SimpleNode::Link { target, text, span } = &parsed.nodes[0] else {
+            panic!("expected a link, got {:?}", parsed.nodes[0]);
+        };
+        assert_eq!(target, "A\u{E000}B");
+        assert_eq!(text, "A\u{E000}B");
+        assert_eq!((span.start, span.end), (0, wt.len()));
+    }
+
+    #[test]
+    fn test_nowiki_ends_link_trail() {
+        let parsed = parse_document("[[Yoda]]<nowiki/>s", &Constructs::default());
+        let [SimpleNode::Link { text, .. }, SimpleNode::Text { value, span }] = &parsed.nodes[..]
+        else {
+            panic!("expected a link and text, got {:?}", parsed.nodes);
+        };
+        assert_eq!(text, "Yoda");
+        assert_eq!(value, "s");
+        assert_eq!(span.end, 18);
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Deserializer};
+
+use crate::categories::CategoryAliases;
+use crate::constructs::Constructs;
+use crate::tagged::NodeFormat;
+
+/// How `parse_checked` and the appearances functions parse. Missing fields keep the defaults,
+/// which are the behavior of `parse` and `parse_appearances` without options.
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(default, rename_all = "camelCase")]
+pub struct ParseOptions {
+    /// Fail on any diagnostic instead of returning the diagnostics next to the result.
+    pub strict: bool,
+    /// Parse the wikitext of each template parameter again on its own, since lists and other
+    /// constructs at the start of a line aren't found in parameters otherwise.
+    pub reparse_parameters: bool,
+    /// `true` for all of them, or the flags of the constructs to keep.
+    #[serde(deserialize_with = "all_or_constructs")]
+    #[cfg_attr(feature = "ts", ts(type = "boolean | Partial<Constructs>"))]
+    pub constructs: Constructs,
+    /// Names of the appearances templates to accept. `parse_appearances` accepts any template
+    /// and `parse_article_appearances` looks for `{{App}}` when missing.
+    pub template_names: Option<Vec<String>>,
+    /// Merged over the default aliases.
+    #[serde(deserialize_with = "CategoryAliases::deserialize_overrides")]
+    pub category_aliases: CategoryAliases,
+    pub deduplicate: bool,
+    /// Format the nodes of the result are serialized in, see `with_node_format`.
+    pub format: NodeFormat,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            strict: true,
+            reparse_parameters: true,
+            constructs: Constructs::default(),
+            template_names: None,
+            category_aliases: CategoryAliases::default(),
+            deduplicate: false,
+            format: NodeFormat::default(),
+        }
+    }
+}
+
+fn all_or_constructs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Constructs, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum AllOrConstructs {
+        All(bool),
+        Constructs(Constructs),
+    }
+
+    Ok(match AllOrConstructs::deserialize(deserializer)? {
+        AllOrConstructs::All(true) => Constructs::all(),
+        AllOrConstructs::All(false) => Constructs::default(),
+        AllOrConstructs::Constructs(constructs) => constructs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_options() {
+        let options: ParseOptions = serde_json::from_value(json!({
+            "strict": false,
+            "constructs": true,
+            "templateNames": ["App", "Appearances"],
+            "categoryAliases": { "beasts": "organisms" },
+        }))
+        .unwrap();
+        assert!(!options.strict);
+        assert!(options.reparse_parameters);
+        assert_eq!(options.constructs, Constructs::all());
+        assert_eq!(options.template_names.unwrap(), ["App", "Appearances"]);
+        // Overrides are merged over the default aliases
+        let (_, category) = options.category_aliases.resolve("c-creatures");
+        assert_eq!(category, "organisms");
+        let (_, category) = options.category_aliases.resolve("c-beasts");
+        assert_eq!(category, "organisms");
+
+        let options: ParseOptions =
+            serde_json::from_value(json!({ "constructs": { "tags": true } })).unwrap();
+        assert!(options.constructs.tags && !options.constructs.headings);
+        assert_eq!(
+            serde_json::from_value::<ParseOptions>(json!({})).unwrap(),
+            ParseOptions::default()
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Characters that `{{!}}`, `{{=}}` and `<nowiki>` sections stand for literally.
+/// Each is replaced by a private use character until the nodes are built, so the parser
+/// doesn't read e.g. the `|` of `{{!}}` as a template parameter separator.
+const ESCAPED: &str = "|=[]{}<>'*#:;&~_-";
+const FIRST_PLACEHOLDER: u32 = 0xE000;
+/// Put before a private use character the wikitext already had, so `restore` keeps it
+/// rather than reading it as a placeholder. It comes right after the placeholders.
+const LITERAL: char = '\u{E011}';
+/// Stands for `<nowiki/>`, which `restore` removes. It keeps the text on both sides apart
+/// while parsing, so e.g. a link trail stops there.
+const BOUNDARY: char = '\u{E012}';
+
+/// Wikitext as the page shows it, with offsets leading back to the original wikitext.
+pub struct Preprocessed {
+    pub text: String,
+    pub source_map: SourceMap,
+}
+
+/// Resolves what `parse_wiki_text` can't:
+/// - removes `<!-- comments -->`, with the line break after them if they are on their own line,
+/// - keeps `{{!}}`, `{{=}}` and the content of `<nowiki>` as text,
+/// - marks the private use characters it uses as placeholders, if the wikitext has them,
+/// - removes `<noinclude>`/`<onlyinclude>` sections and `<includeonly>` tags, keeping their content.
+///
+/// Comments are kept with `keep_comments`, for callers that want them as nodes.
+/// Escaped characters are brought back with `restore`.
+pub fn preprocess(wikitext: &str, keep_comments: bool) -> Preprocessed {
+    let mut builder = Builder::default();
+    let mut pos = 0;
+    while let Some(next) = wikitext[pos..]
+        .find(|c| matches!(c, '<' | '{') || is_placeholder(c))
+        .map(|i| pos + i)
+    {
+        builder.copy(pos, &wikitext[pos..next]);
+        pos = next;
+        let rest = &wikitext[pos..];
+        if rest.starts_with("<!--") && !keep_comments {
+            let end = rest.find("-->").map_or(wikitext.len(), |i| pos + i + 3);
+            // Skipped text makes the next copy start a new segment, since it isn't contiguous
+            pos = comment_line_end(wikitext, pos, end);
+        } else if let Some(replacement) = ["{{!}}", "{{=}}"]
+            .iter()
+            .position(|word| rest.starts_with(word))
+            .map(|i| ['|', '='][i])
+        {
+            builder.replace(pos..pos + 5, &escape(replacement).to_string());
+            pos += 5;
+        } else if let Some(tag) = Tag::parse(rest) {
+            pos = builder.tag(wikitext, pos, &tag);
+        } else if let Some(c) = rest.chars().next().filter(|&c| is_placeholder(c)) {
+            builder.replace(pos..pos + c.len_utf8(), &literal(c));
+            pos += c.len_utf8();
+        } else {
+            builder.copy(pos, &rest[..1]);
+            pos += 1;
+        }
+    }
+    builder.copy(pos, &wikitext[pos..]);
+    Preprocessed {
+        text: builder.text,
+        source_map: SourceMap {
+            segments: builder.segments,
+        },
+    }
+}
+
+/// Brings back the characters `preprocess` escaped in `text`.
+pub fn restore(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_placeholder) {
+        return Cow::Borrowed(text);
+    }
+    let mut restored = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            LITERAL => restored.extend(chars.next()),
+            BOUNDARY => (),
+            c => restored.push(unescape(c).unwrap_or(c)),
+        }
+    }
+    Cow::Owned(restored)
+}
+
+fn is_placeholder(c: char) -> bool {
+    (FIRST_PLACEHOLDER..=BOUNDARY as u32).contains(&(c as u32))
+}
+
+/// `c`, a placeholder character found in the wikitext, marked to be kept as is.
+fn literal(c: char) -> String {
+    [LITERAL, c].iter().collect()
+}
+
+fn escape(c: char) -> char {
+    ESCAPED
+        .find(c)
+        .and_then(|i| char::from_u32(FIRST_PLACEHOLDER + i as u32))
+        .unwrap_or(c)
+}
+
+fn unescape(c: char) -> Option<char> {
+    let index = (c as u32).checked_sub(FIRST_PLACEHOLDER)?;
+    ESCAPED.chars().nth(index as usize)
+}
+
+/// A comment alone on its line takes the line break with it, so it doesn't split e.g. a list.
+fn comment_line_end(wikitext: &str, start: usize, end: usize) -> usize {
+    let at_line_start = start == 0 || wikitext[..start].ends_with('\n');
+    let rest = &wikitext[end..];
+    let blank = rest.trim_start_matches([' ', '\t']);
+    if at_line_start && blank.starts_with('\n') {
+        end + (rest.len() - blank.len()) + 1
+    } else {
+        end
+    }
+}
+
+/// `<name>`, `</name>` or `<name/>` without attributes.
+struct Tag {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    len: usize,
+}
+
+impl Tag {
+    fn parse(text: &str) -> Option<Tag> {
+        let inner = text.strip_prefix('<')?;
+        let (closing, inner) = match inner.strip_prefix('/') {
+            Some(inner) => (true, inner),
+            None => (false, inner),
+        };
+        let name_len = inner
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(inner.len());
+        let after_name = inner[name_len..].trim_start();
+        let (self_closing, after_name) = match after_name.strip_prefix('/') {
+            Some(after_name) => (true, after_name),
+            None => (false, after_name),
+        };
+        after_name.strip_prefix('>')?;
+        Some(Tag {
+            name: inner[..name_len].to_ascii_lowercase(),
+            closing,
+            self_closing,
+            len: text.len() - after_name.len() + 1,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    text: String,
+    segments: Vec<Segment>,
+}
+
+impl Builder {
+    /// Copies `text`, found at `src` in the original wikitext.
+    fn copy(&mut self, src: usize, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let contiguous = self.segments.last().is_some_and(|segment| {
+            segment.replaced_end.is_none() && segment.src + self.text.len() - segment.out == src
+        });
+        if !contiguous {
+            self.segments.push(Segment {
+                out: self.text.len(),
+                src,
+                replaced_end: None,
+            });
+        }
+        self.text.push_str(text);
+    }
+
+    fn replace(&mut self, src: Range<usize>, replacement: &str) {
+        self.segments.push(Segment {
+            out: self.text.len(),
+            src: src.start,
+            replaced_end: Some(src.end),
+        });
+        self.text.push_str(replacement);
+    }
+
+    /// Handles the tag at `pos` and returns where to continue.
+    fn tag(&mut self, wikitext: &str, pos: usize, tag: &Tag) -> usize {
+        let after = pos + tag.len;
+        match tag.name.as_str() {
+            "nowiki" if !tag.closing && !tag.self_closing => {
+                let Some(content_len) = find_closing(&wikitext[after..], "nowiki") else {
+                    // An unclosed `<nowiki>` is shown as is
+                    self.copy(pos, &wikitext[pos..after]);
+                    return after;
+                };
+                for (i, c) in wikitext[after..after + content_len].char_indices() {
+                    let src = after + i;
+                    if is_placeholder(c) {
+                        self.replace(src..src + c.len_utf8(), &literal(c));
+                    } else if escape(c) == c {
+                        self.copy(src, c.encode_utf8(&mut [0; 4]));
+                    } else {
+                        self.replace(src..src + 1, &escape(c).to_string());
+                    }
+                }
+                after + content_len + "</nowiki>".len()
+            }
+            "noinclude" | "onlyinclude" if !tag.closing && !tag.self_closing => {
+                find_closing(&wikitext[after..], &tag.name)
+                    .map_or(wikitext.len(), |len| after + len + tag.name.len() + 3)
+            }
+            "nowiki" if tag.self_closing => {
+                self.replace(pos..after, &BOUNDARY.to_string());
+                after
+            }
+            "nowiki" | "includeonly" | "noinclude" | "onlyinclude" => after,
+            _ => {
+                self.copy(pos, &wikitext[pos..after]);
+                after
+            }
+        }
+    }
+}
+
+/// Length of the text before `</name>`, ignoring case.
+fn find_closing(text: &str, name: &str) -> Option<usize> {
+    text.to_ascii_lowercase().find(&format!("</{}>", name))
+}
+
+/// Maps offsets of preprocessed wikitext back to the original wikitext.
+pub struct SourceMap {
+    /// Sorted by `out`, the offset in the preprocessed text a segment starts at.
+    segments: Vec<Segment>,
+}
+
+struct Segment {
+    out: usize,
+    src: usize,
+    /// End of the original text a replacement stands for, `None` for copied text.
+    replaced_end: Option<usize>,
+}
+
+impl SourceMap {
+    pub fn to_source(&self, offset: usize) -> usize {
+        let index = self
+            .segments
+            .partition_point(|segment| segment.out <= offset);
+        let Some(segment) = index.checked_sub(1).map(|i| &self.segments[i]) else {
+            return offset;
+        };
+        match segment.replaced_end {
+            None => segment.src + offset - segment.out,
+            // Offsets inside a replacement point at its start or, past the start, at its end
+            Some(_) if offset == segment.out => segment.src,
+            Some(end) => end,
+        }
+    }
+
+    /// Maps an exclusive end offset, which belongs to the text before it. So the end of
+    /// `[[Cordé]]` followed by a removed comment stays before the comment.
+    pub fn end_to_source(&self, offset: usize) -> usize {
+        let Some(last) = offset.checked_sub(1) else {
+            return self.to_source(offset);
+        };
+        let index = self.segments.partition_point(|segment| segment.out <= last);
+        match index.checked_sub(1).map(|i| &self.segments[i]) {
+            Some(Segment {
+                replaced_end: Some(end),
+                ..
+            }) => *end,
+            Some(segment) => segment.src + offset - segment.out,
+            None => offset,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comments() {
+        let wt = "*[[A]]\n<!-- B -->\n*[[C]] <!-- D -->\n";
+        let preprocessed = preprocess(wt, false);
+        assert_eq!(preprocessed.text, "*[[A]]\n*[[C]] \n");
+        let c = preprocessed.text.find('C').unwrap();
+        assert_eq!(preprocessed.source_map.to_source(c), wt.find('C').unwrap());
+        assert_eq!(preprocess(wt, true).text, wt);
+    }
+
+    #[test]
+    fn test_escapes() {
+        let wt = "{{App\n|c-characters=\n*[[A]]\n{{!}}\n*[[B]]}}<nowiki>[[C]]</nowiki>{{=}}";
+        let preprocessed = preprocess(wt, false);
+        assert!(!preprocessed.text.contains("{{!}}"));
+        assert!(!preprocessed.text.contains("[[C]]"));
+        assert_eq!(
+            restore(&preprocessed.text),
+            "{{App\n|c-characters=\n*[[A]]\n|\n*[[B]]}}[[C]]="
+        );
+        let b = preprocessed.text.find("[[B").unwrap();
+        assert_eq!(
+            preprocessed.source_map.to_source(b),
+            wt.find("[[B").unwrap()
+        );
+        let end = preprocessed.text.len();
+        assert_eq!(preprocessed.source_map.end_to_source(end), wt.len());
+    }
+
+    #[test]
+    fn test_placeholders_in_wikitext() {
+        let wt = "[[A\u{E000}B]]<nowiki>\u{E011}</nowiki>";
+        let preprocessed = preprocess(wt, false);
+        assert_eq!(restore(&preprocessed.text), "[[A\u{E000}B]]\u{E011}");
+        let end = preprocessed.text.find("]]").unwrap();
+        assert_eq!(
+            preprocessed.source_map.to_source(end),
+            wt.find("]]").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_inclusion_tags() {
+        let wt = "<noinclude>{{Top}}</noinclude>Text<includeonly>[[Category:A]]</includeonly>\n<ONLYINCLUDE>B</onlyinclude>";
+        let preprocessed = preprocess(wt, false);
+        assert_eq!(preprocessed.text, "Text[[Category:A]]\n");
+        assert_eq!(
+            preprocessed.source_map.to_source(0),
+            wt.find("Text").unwrap()
+        );
+        let nowiki = preprocess("[[Yoda]]<nowiki/>s", false);
+        assert_eq!(nowiki.text, "[[Yoda]]\u{E012}s");
+        assert_eq!(restore(&nowiki.text), "[[Yoda]]s");
+        // Tags the preprocessor doesn't know are left to the parser
+        assert_eq!(preprocess("<ref>A</ref>", false).text, "<ref>A</ref>");
+    }
+}
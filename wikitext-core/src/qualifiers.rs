@@ -0,0 +1,54 @@
+use serde::Serialize;
+
+use crate::{simple_nodes_to_text, SimpleTemplate};
+
+/// Meaning of a Wookieepedia template following an appearance, e.g. `{{1st}}` or `{{Mo}}`.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Qualifier {
+    /// `{{1st}}`
+    FirstAppearance,
+    /// `{{1stID|name}}`, first appearance under the given name.
+    FirstIdentified { name: Option<String> },
+    /// `{{Mo}}`
+    MentionedOnly,
+    /// `{{Imo}}`
+    IndirectMention,
+    /// `{{Flash}}`
+    Flashback,
+    /// `{{Hologram}}`
+    Hologram,
+    /// `{{Vision}}`
+    Vision,
+    /// `{{C|text}}`, a free-text comment.
+    Comment { text: String },
+}
+
+impl Qualifier {
+    /// Returns `None` for templates that aren't appearance qualifiers.
+    pub fn from_template(template: &SimpleTemplate) -> Option<Self> {
+        let positional_text = || {
+            template
+                .parameters
+                .iter()
+                .find(|param| param.name.is_none())
+                .map(|param| simple_nodes_to_text(&param.value).trim().to_string())
+        };
+        match template.name.trim().to_lowercase().as_str() {
+            "1st" => Some(Qualifier::FirstAppearance),
+            "1stid" => Some(Qualifier::FirstIdentified {
+                name: positional_text().filter(|name| !name.is_empty()),
+            }),
+            "mo" => Some(Qualifier::MentionedOnly),
+            "imo" => Some(Qualifier::IndirectMention),
+            "flash" => Some(Qualifier::Flashback),
+            "hologram" => Some(Qualifier::Hologram),
+            "vision" => Some(Qualifier::Vision),
+            "c" => Some(Qualifier::Comment {
+                text: positional_text().unwrap_or_default(),
+            }),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Finds templates, parameters and links anywhere in a node tree with selectors written like the
+//! wikitext they match, e.g. `{{App|c-characters}} [[*]]` for every link in the `c-characters`
+//! parameter of `{{App}}`.
+//!
+//! A selector is a whitespace-separated list of steps, each matching inside the previous one at any depth:
+//! - `{{Name}}` matches templates by normalized name, `{{Name|param}}` the named parameters of one.
+//! - `[[Target]]` matches links by normalized target.
+//!
+//! A name, parameter or target ending with `*` is a prefix, and `*` alone matches anything.
+
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::title::{normalize_link_target, normalize_template_name};
+use crate::{Error, Result, SimpleNode, SimpleParameter, SimpleTemplate};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Pattern {
+    Any,
+    Exact(String),
+    Prefix(String),
+}
+
+impl Pattern {
+    /// Parses `*`, `prefix*` or an exact value, normalizing the value with `normalize`.
+    fn parse(raw: &str, normalize: impl Fn(&str) -> String) -> Self {
+        match raw.trim().strip_suffix('*') {
+            Some("") => Pattern::Any,
+            Some(prefix) => Pattern::Prefix(normalize(prefix)),
+            None => Pattern::Exact(normalize(raw)),
+        }
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            Pattern::Any => true,
+            Pattern::Exact(exact) => value == exact,
+            Pattern::Prefix(prefix) => value.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Step {
+    /// Templates named `name`, or only their parameters named `parameter` when given.
+    Template {
+        name: Pattern,
+        parameter: Option<Pattern>,
+    },
+    Link {
+        target: Pattern,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Selector {
+    pub steps: Vec<Step>,
+}
+
+impl FromStr for Selector {
+    type Err = Error;
+
+    fn from_str(selector: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidSelector(format!("{reason} in {selector:?}"));
+        let mut steps = Vec::new();
+        let mut rest = selector.trim_start();
+        while !rest.is_empty() {
+            let (step, after) = if let Some(template) = rest.strip_prefix("{{") {
+                let (inner, after) = template
+                    .split_once("}}")
+                    .ok_or_else(|| invalid("Unclosed {{"))?;
+                let (name, parameter) = match inner.split_once('|') {
+                    Some((name, parameter)) => (name, Some(parameter)),
+                    None => (inner, None),
+                };
+                let step = Step::Template {
+                    name: Pattern::parse(name, |name| normalize_template_name(name).into_owned()),
+                    parameter: parameter
+                        .map(|parameter| Pattern::parse(parameter, |name| name.trim().to_string())),
+                };
+                (step, after)
+            } else if let Some(link) = rest.strip_prefix("[[") {
+                let (target, after) = link
+                    .split_once("]]")
+                    .ok_or_else(|| invalid("Unclosed [["))?;
+                let step = Step::Link {
+                    target: Pattern::parse(target, |target| {
+                        normalize_link_target(target).into_owned()
+                    }),
+                };
+                (step, after)
+            } else {
+                return Err(invalid("Expected {{ or [["));
+            };
+            steps.push(step);
+            rest = after.trim_start();
+        }
+        if steps.is_empty() {
+            return Err(invalid("Empty selector"));
+        }
+        Ok(Selector { steps })
+    }
+}
+
+/// Node or template parameter matched by the last step of a selector.
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(untagged)]
+pub enum QueryMatch<'a> {
+    Node(&'a SimpleNode<'a>),
+    Parameter(&'a SimpleParameter<'a>),
+}
+
+/// Matches of `selector` in `nodes`, in the order they appear in the wikitext.
+pub fn query<'a>(nodes: &'a [SimpleNode<'a>], selector: &Selector) -> Vec<QueryMatch<'a>> {
+    let mut matches = Vec::new();
+    query_nodes(nodes, &selector.steps, &mut matches);
+    matches
+}
+
+fn query_nodes<'a>(nodes: &'a [SimpleNode<'a>], steps: &[Step], matches: &mut Vec<QueryMatch<'a>>) {
+    for node in nodes {
+        query_node(node, steps, matches);
+    }
+}
+
+fn query_node<'a>(node: &'a SimpleNode<'a>, steps: &[Step], matches: &mut Vec<QueryMatch<'a>>) {
+    let [step, rest @ ..] = steps else {
+        return;
+    };
+    match (node, step) {
+        (SimpleNode::Template(template), Step::Template { name, parameter })
+            if name.matches(&template.name) =>
+        {
+            match parameter {
+                Some(parameter) => {
+                    for param in &template.parameters {
+                        let selected = param
+                            .name
+                            .as_deref()
+                            .is_some_and(|name| parameter.matches(name));
+                        if selected && rest.is_empty() {
+                            matches.push(QueryMatch::Parameter(param));
+                        }
+                        // The other parameters can still hold matches of the whole selector
+                        let steps = if selected && !rest.is_empty() {
+                            rest
+                        } else {
+                            steps
+                        };
+                        query_nodes(&param.value, steps, matches);
+                    }
+                }
+                None => {
+                    if rest.is_empty() {
+                        matches.push(QueryMatch::Node(node));
+                    }
+                    let steps = if rest.is_empty() { steps } else { rest };
+                    query_template(template, steps, matches);
+                }
+            }
+        }
+        (SimpleNode::Link { target, .. }, Step::Link { target: pattern })
+            if pattern.matches(target) =>
+        {
+            if rest.is_empty() {
+                matches.push(QueryMatch::Node(node));
+            }
+        }
+        _ => query_children(node, steps, matches),
+    }
+}
+
+fn query_template<'a>(
+    template: &'a SimpleTemplate<'a>,
+    steps: &[Step],
+    matches: &mut Vec<QueryMatch<'a>>,
+) {
+    for param in &template.parameters {
+        query_nodes(&param.value, steps, matches);
+    }
+}
+
+fn query_children<'a>(node: &'a SimpleNode<'a>, steps: &[Step], matches: &mut Vec<QueryMatch<'a>>) {
+    match node {
+        SimpleNode::List { items, .. } => {
+            for item in items {
+                query_nodes(item, steps, matches);
+            }
+        }
+        SimpleNode::DefinitionList { items, .. } => {
+            for item in items {
+                query_nodes(&item.nodes, steps, matches);
+            }
+        }
+        SimpleNode::Template(template) => query_template(template, steps, matches),
+        SimpleNode::ExternalLink { text: nodes, .. }
+        | SimpleNode::Image { text: nodes, .. }
+        | SimpleNode::Heading { nodes, .. }
+        | SimpleNode::Tag { nodes, .. }
+        | SimpleNode::Preformatted { nodes, .. }
+        | SimpleNode::Parameter {
+            default: Some(nodes),
+            ..
+        } => query_nodes(nodes, steps, matches),
+        SimpleNode::Table(table) => {
+            for caption in &table.captions {
+                query_nodes(caption, steps, matches);
+            }
+            for row in &table.rows {
+                for cell in &row.cells {
+                    query_nodes(&cell.content, steps, matches);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constructs::Constructs;
+    use crate::parse_document;
+
+    fn names(matches: &[QueryMatch]) -> Vec<String> {
+        matches
+            .iter()
+            .map(|found| match found {
+                QueryMatch::Node(SimpleNode::Template(template)) => template.name.to_string(),
+                QueryMatch::Node(SimpleNode::Link { target, .. }) => target.to_string(),
+                QueryMatch::Node(node) => panic!("unexpected match {node:?}"),
+                QueryMatch::Parameter(param) => {
+                    param.name.as_deref().unwrap_or_default().to_string()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_selector() {
+        let selector: Selector = "{{app|c-*}}  [[battle_of*]] {{*}}".parse().unwrap();
+        assert_eq!(
+            selector.steps,
+            [
+                Step::Template {
+                    name: Pattern::Exact("App".to_string()),
+                    parameter: Some(Pattern::Prefix("c-".to_string())),
+                },
+                Step::Link {
+                    target: Pattern::Prefix("Battle of".to_string())
+                },
+                Step::Template {
+                    name: Pattern::Any,
+                    parameter: None
+                },
+            ]
+        );
+        for invalid in ["", "{{App", "App", "{{App}} [[Yoda"] {
+            assert!(
+                matches!(invalid.parse::<Selector>(), Err(Error::InvalidSelector(_))),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_query() {
+        let wt = "{{Top}}
+'''Tales''' is an anthology.<ref>{{StoryCite|book=[[Tales]]}}</ref>
+{{App
+|c-characters=
+*[[Rex]] {{C|[[Clone Wars]]}}
+*[[Cody]]
+|c-events=
+*[[Clone Wars]]
+}}";
+        let parsed = parse_document(wt, &Constructs::all());
+        let find = |selector: &str| names(&query(&parsed.nodes, &selector.parse().unwrap()));
+
+        assert_eq!(
+            find("{{App|c-characters}} [[*]]"),
+            ["Rex", "Clone Wars", "Cody"]
+        );
+        assert_eq!(find("{{App}} [[Clone*]]"), ["Clone Wars", "Clone Wars"]);
+        assert_eq!(find("{{App|c-*}}"), ["c-characters", "c-events"]);
+        // Templates are found inside tags and other templates
+        assert_eq!(find("{{*}}"), ["Top", "StoryCite", "App", "C"]);
+        assert_eq!(find("{{storyCite}} [[*]]"), ["Tales"]);
+        assert_eq!(find("{{C}} [[*]]"), ["Clone Wars"]);
+        assert!(find("[[Rex]] [[*]]").is_empty());
+
+        let found = query(&parsed.nodes, &"[[Cody]]".parse().unwrap());
+        let QueryMatch::Node(SimpleNode::Link { span, .. }) = found[0] else {
+            panic!("expected a link, got {:?}", found[0]);
+        };
+        assert_eq!(&wt[span.start..span.end], "[[Cody]]");
+    }
+}
@@ -0,0 +1,83 @@
+use crate::title::normalize_template_name;
+use crate::{simple_nodes_to_text, SimpleNode, SimpleTemplate};
+
+/// Appearances template found in an article, with the headings of the section it is in.
+/// The template borrows from `'a` nodes, parsed from a `'b` wikitext.
+pub struct SectionTemplate<'a, 'b> {
+    /// Headings enclosing the template, outermost first.
+    pub path: Vec<String>,
+    pub template: &'a SimpleTemplate<'b>,
+}
+
+/// Every template named one of `names` in `nodes`, including ones inside other templates' parameters
+/// or tags like `<tabber>`. Names are compared case-insensitively, with or without `Template:`.
+pub fn find_section_templates<'a, 'b>(
+    nodes: &'a [SimpleNode<'b>],
+    names: &[String],
+) -> Vec<SectionTemplate<'a, 'b>> {
+    let names: Vec<String> = names.iter().map(|name| template_key(name)).collect();
+    let mut found = Vec::new();
+    walk(nodes, &names, &mut Vec::new(), &mut found);
+    found
+}
+
+fn template_key(name: &str) -> String {
+    normalize_template_name(name).to_lowercase()
+}
+
+/// `headings` holds the level and text of the enclosing headings.
+fn walk<'a, 'b>(
+    nodes: &'a [SimpleNode<'b>],
+    names: &[String],
+    headings: &mut Vec<(u8, String)>,
+    found: &mut Vec<SectionTemplate<'a, 'b>>,
+) {
+    for node in nodes {
+        match node {
+            SimpleNode::Heading { level, nodes, .. } => {
+                while headings.last().is_some_and(|(last, _)| last >= level) {
+                    headings.pop();
+                }
+                headings.push((*level, simple_nodes_to_text(nodes).trim().to_string()));
+            }
+            SimpleNode::Template(template) if names.contains(&template_key(&template.name)) => {
+                found.push(SectionTemplate {
+                    path: headings.iter().map(|(_, text)| text.clone()).collect(),
+                    template,
+                });
+            }
+            SimpleNode::Template(template) => {
+                for param in &template.parameters {
+                    walk(&param.value, names, headings, found);
+                }
+            }
+            SimpleNode::Tag { nodes, .. } => walk(nodes, names, headings, found),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constructs::Constructs;
+    use crate::parse_document;
+
+    #[test]
+    fn test_templates_by_section() {
+        let wt = "{{Top}}\n== Stories ==\n=== [[The Clone]] ===\n==== Appearances ====\n{{app\n|c-characters=\n*[[Rex]]\n}}\n=== The Droid ===\n{{Tabber|1={{Template:App\n|c-droids=\n*[[R2-D2]]\n}}}}\n== Sources ==";
+        let constructs = Constructs {
+            headings: true,
+            ..Constructs::default()
+        };
+        let parsed = parse_document(wt, &constructs);
+        let found = find_section_templates(&parsed.nodes, &["APP".to_string()]);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].path, ["Stories", "The Clone", "Appearances"]);
+        assert_eq!(found[1].path, ["Stories", "The Droid"]);
+        assert_eq!(
+            found[1].template.parameters[0].name.as_deref(),
+            Some("c-droids")
+        );
+    }
+}
@@ -0,0 +1,460 @@
+use crate::title::Title;
+use crate::{SimpleDefinitionItem, SimpleNode, SimpleParameter, SimpleTable, SimpleTemplate};
+
+/// Writes nodes back out as canonical wikitext: links and templates use their normalized names,
+/// block constructs sit on their own lines, and multi-line template parameters each start a line.
+///
+/// Parsing the output gives the same nodes, except for spans and how text is split between `Text`
+/// nodes. Text is escaped where it would be read as markup: `<`, `&` and runs of `'` everywhere,
+/// doubled brackets and braces with `<nowiki>`, and `|` and `=` in template parameters and link
+/// text with `{{!}}` and `{{=}}`. Text that is only markup elsewhere, like a `|` in a table cell
+/// moved out of its table, isn't escaped.
+pub fn to_wikitext(nodes: &[SimpleNode]) -> String {
+    write_in(nodes, Context::Body)
+}
+
+/// Canonical wikitext of a single template, see `to_wikitext`.
+pub fn template_to_wikitext(template: &SimpleTemplate) -> String {
+    let mut writer = Writer::default();
+    writer.template(template);
+    writer.out
+}
+
+fn write_in(nodes: &[SimpleNode], context: Context) -> String {
+    let mut writer = Writer {
+        context,
+        ..Writer::default()
+    };
+    writer.nodes(nodes);
+    writer.out
+}
+
+/// Where text is written, which decides what it has to escape.
+#[derive(Default, Clone, Copy, PartialEq)]
+enum Context {
+    #[default]
+    Body,
+    /// A template parameter value, where `|` starts the next parameter and, unless the parameter
+    /// is named already, `=` would name it.
+    Parameter { named: bool },
+    /// Link or image text, where `|` separates the target or the image options.
+    Link,
+}
+
+#[derive(Default)]
+struct Writer {
+    out: String,
+    context: Context,
+    /// `*`, `#`, `;` and `:` of the enclosing lists.
+    list_prefix: String,
+    /// A block construct ended, so whatever follows goes on the next line.
+    after_block: bool,
+}
+
+impl Writer {
+    fn nodes(&mut self, nodes: &[SimpleNode]) {
+        let mut nodes = nodes.iter().peekable();
+        while let Some(node) = nodes.next() {
+            let SimpleNode::Text { value, .. } = node else {
+                self.node(node);
+                continue;
+            };
+            // Text is escaped as a whole, since e.g. `&amp;` followed by `lt;` is split in two nodes
+            let mut text = value.to_string();
+            while let Some(SimpleNode::Text { value, .. }) = nodes.peek() {
+                text.push_str(value);
+                nodes.next();
+            }
+            if std::mem::take(&mut self.after_block) {
+                self.out.push('\n');
+            }
+            self.text(&text);
+        }
+    }
+
+    fn node(&mut self, node: &SimpleNode) {
+        if std::mem::take(&mut self.after_block) {
+            self.out.push('\n');
+        }
+        match node {
+            SimpleNode::Text { value, .. } => self.text(value),
+            SimpleNode::Link { target, text, .. } => {
+                self.out.push_str("[[");
+                // A leading colon keeps e.g. `[[:Category:Jedi]]` a link instead of categorizing
+                if matches!(
+                    Title::parse(target).namespace.as_deref(),
+                    Some("File" | "Category")
+                ) {
+                    self.out.push(':');
+                }
+                self.out.push_str(target);
+                if text != target {
+                    self.out.push('|');
+                    self.with_context(Context::Link, |writer| writer.text(text));
+                }
+                self.out.push_str("]]");
+            }
+            SimpleNode::ExternalLink { url, text, .. } => {
+                self.out.push('[');
+                self.out.push_str(url);
+                if !text.is_empty() {
+                    self.out.push(' ');
+                    self.nodes(text);
+                }
+                self.out.push(']');
+            }
+            SimpleNode::Image { target, text, .. } => {
+                self.out.push_str("[[");
+                self.out.push_str(target);
+                if !text.is_empty() {
+                    self.out.push('|');
+                    self.with_context(Context::Link, |writer| writer.nodes(text));
+                }
+                self.out.push_str("]]");
+            }
+            SimpleNode::Category {
+                target, sort_key, ..
+            } => {
+                self.out.push_str("[[");
+                self.out.push_str(target);
+                if let Some(sort_key) = sort_key {
+                    self.out.push('|');
+                    self.out.push_str(sort_key);
+                }
+                self.out.push_str("]]");
+            }
+            SimpleNode::Template(template) => self.template(template),
+            SimpleNode::Bold { .. } => self.quotes(3),
+            SimpleNode::Italic { .. } => self.quotes(2),
+            SimpleNode::BoldItalic { .. } => self.quotes(5),
+            SimpleNode::List { ordered, items, .. } => {
+                let marker = if *ordered { '#' } else { '*' };
+                for item in items {
+                    self.list_item(marker, item);
+                }
+                self.after_block = true;
+            }
+            SimpleNode::DefinitionList { items, .. } => {
+                for SimpleDefinitionItem { term, nodes, .. } in items {
+                    self.list_item(if *term { ';' } else { ':' }, nodes);
+                }
+                self.after_block = true;
+            }
+            SimpleNode::Heading { level, nodes, .. } => {
+                let equals = "=".repeat(*level as usize);
+                self.start_line();
+                self.out.push_str(&equals);
+                self.nodes(nodes);
+                self.out.push_str(&equals);
+                self.after_block = true;
+            }
+            SimpleNode::Tag {
+                name,
+                attributes,
+                nodes,
+                ..
+            } => {
+                self.start_tag(name, attributes);
+                self.nodes(nodes);
+                self.out.push_str(&format!("</{name}>"));
+            }
+            SimpleNode::StartTag {
+                name, attributes, ..
+            } => self.start_tag(name, attributes),
+            SimpleNode::EndTag { name, .. } => self.out.push_str(&format!("</{name}>")),
+            SimpleNode::Table(table) => self.table(table),
+            SimpleNode::Preformatted { nodes, .. } => {
+                self.start_line();
+                let content = write_in(nodes, self.context);
+                self.out.push(' ');
+                self.out.push_str(&content.replace('\n', "\n "));
+                self.after_block = true;
+            }
+            SimpleNode::Parameter { name, default, .. } => {
+                self.out.push_str("{{{");
+                self.out.push_str(name);
+                if let Some(default) = default {
+                    self.out.push('|');
+                    self.nodes(default);
+                }
+                self.out.push_str("}}}");
+            }
+            SimpleNode::MagicWord { name, .. } => self.out.push_str(&format!("__{name}__")),
+            SimpleNode::Redirect { target, .. } => {
+                self.out.push_str(&format!("#REDIRECT [[{target}]]"));
+                self.after_block = true;
+            }
+            SimpleNode::Comment { text, .. } => self.out.push_str(&format!("<!--{text}-->")),
+            SimpleNode::HorizontalDivider { .. } => {
+                self.start_line();
+                self.out.push_str("----");
+                self.after_block = true;
+            }
+            SimpleNode::ParagraphBreak { .. } => self.out.push_str("\n\n"),
+        }
+    }
+
+    fn text(&mut self, value: &str) {
+        let mut chars = value.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let doubled = chars.peek().is_some_and(|(_, next)| *next == c);
+            match c {
+                '<' => self.out.push_str("&lt;"),
+                '&' if looks_like_entity(&value[i + 1..]) => self.out.push_str("&amp;"),
+                // Two quotes in a row would start bold or italic text
+                '\'' if doubled || self.out.ends_with('\'') => self.out.push_str("&apos;"),
+                '[' | ']' | '{' | '}' if doubled => {
+                    chars.next();
+                    self.out.push_str(&format!("<nowiki>{c}{c}</nowiki>"));
+                }
+                // A lone brace next to a template would make it e.g. `{{{1}}}`
+                '{' | '}' if i == 0 || i + 1 == value.len() => {
+                    self.out.push_str(&format!("<nowiki>{c}</nowiki>"))
+                }
+                '|' if self.context != Context::Body => self.out.push_str("{{!}}"),
+                '=' if self.context == (Context::Parameter { named: false }) => {
+                    self.out.push_str("{{=}}")
+                }
+                c => self.out.push(c),
+            }
+        }
+    }
+
+    fn quotes(&mut self, count: usize) {
+        if self.out.ends_with('\'') {
+            self.out.pop();
+            self.out.push_str("&apos;");
+        }
+        self.out.push_str(&"'".repeat(count));
+    }
+
+    fn with_context(&mut self, context: Context, write: impl FnOnce(&mut Self)) {
+        let outer = std::mem::replace(&mut self.context, context);
+        write(self);
+        self.context = outer;
+    }
+
+    fn start_line(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn list_item(&mut self, marker: char, nodes: &[SimpleNode]) {
+        self.after_block = false;
+        self.start_line();
+        self.list_prefix.push(marker);
+        self.out.push_str(&self.list_prefix);
+        self.nodes(nodes);
+        self.list_prefix.pop();
+    }
+
+    fn template(&mut self, template: &SimpleTemplate) {
+        // Block values like appearance lists need the `|` of the next parameter on a new line
+        let multiline = template
+            .parameters
+            .iter()
+            .any(|param| param.value.iter().any(is_block));
+        self.out.push_str("{{");
+        self.out.push_str(&template.name);
+        for SimpleParameter { name, value, .. } in &template.parameters {
+            let context = Context::Parameter {
+                named: name.is_some(),
+            };
+            if multiline {
+                self.out.push('\n');
+            }
+            self.out.push('|');
+            if let Some(name) = name {
+                self.out.push_str(name);
+                self.out.push('=');
+            }
+            // Parameter values are parsed on their own, so they start at the beginning of a line
+            if value.first().is_some_and(is_block) {
+                self.out.push('\n');
+            }
+            self.out.push_str(&write_in(value, context));
+        }
+        if multiline {
+            self.out.push('\n');
+        }
+        self.out.push_str("}}");
+    }
+
+    fn start_tag(&mut self, name: &str, attributes: &str) {
+        self.out.push('<');
+        self.out.push_str(name);
+        if !attributes.is_empty() {
+            self.out.push(' ');
+            self.out.push_str(attributes);
+        }
+        self.out.push('>');
+    }
+
+    fn table(&mut self, table: &SimpleTable) {
+        self.start_line();
+        self.out.push_str("{|");
+        if !table.attributes.is_empty() {
+            self.out.push(' ');
+            self.out.push_str(&table.attributes);
+        }
+        for caption in &table.captions {
+            self.out.push_str("\n|+");
+            self.cell_content(caption);
+        }
+        for (index, row) in table.rows.iter().enumerate() {
+            if index > 0 || !row.attributes.is_empty() {
+                self.out.push_str("\n|-");
+                if !row.attributes.is_empty() {
+                    self.out.push(' ');
+                    self.out.push_str(&row.attributes);
+                }
+            }
+            for cell in &row.cells {
+                self.out.push('\n');
+                self.out.push(if cell.heading { '!' } else { '|' });
+                if let Some(attributes) = &cell.attributes {
+                    self.out.push_str(attributes);
+                    self.out.push('|');
+                }
+                self.cell_content(&cell.content);
+            }
+        }
+        self.out.push_str("\n|}");
+        self.after_block = true;
+    }
+
+    fn cell_content(&mut self, nodes: &[SimpleNode]) {
+        if nodes.first().is_some_and(is_block) {
+            self.out.push('\n');
+        }
+        self.nodes(nodes);
+        self.after_block = false;
+    }
+}
+
+fn is_block(node: &SimpleNode) -> bool {
+    matches!(
+        node,
+        SimpleNode::List { .. }
+            | SimpleNode::DefinitionList { .. }
+            | SimpleNode::Heading { .. }
+            | SimpleNode::Table(_)
+            | SimpleNode::Preformatted { .. }
+            | SimpleNode::HorizontalDivider { .. }
+    )
+}
+
+/// Whether `&` followed by `rest` would be read as a character entity.
+fn looks_like_entity(rest: &str) -> bool {
+    rest.split_once(';').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '#')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::Value;
+
+    use super::*;
+    use crate::constructs::Constructs;
+    use crate::parse_document;
+
+    /// Nodes as JSON without spans, with adjacent text merged.
+    fn canonical(nodes: &[SimpleNode]) -> Value {
+        fn strip(value: &mut Value) {
+            match value {
+                Value::Object(map) => {
+                    map.remove("span");
+                    map.values_mut().for_each(strip);
+                }
+                Value::Array(values) => {
+                    values.iter_mut().for_each(strip);
+                    let mut merged: Vec<Value> = Vec::new();
+                    for value in values.drain(..) {
+                        let text = value.pointer("/Text/value").and_then(Value::as_str);
+                        match (text, merged.last_mut()) {
+                            (Some(text), Some(Value::Object(previous)))
+                                if previous.contains_key("Text") =>
+                            {
+                                let previous =
+                                    previous["Text"]["value"].as_str().unwrap().to_string();
+                                *merged.last_mut().unwrap() =
+                                    serde_json::json!({ "Text": { "value": previous + text } });
+                            }
+                            _ => merged.push(value),
+                        }
+                    }
+                    *values = merged;
+                }
+                _ => (),
+            }
+        }
+        let mut value = serde_json::to_value(nodes).unwrap();
+        strip(&mut value);
+        value
+    }
+
+    fn assert_round_trip(wikitext: &str, constructs: &Constructs) {
+        let nodes = parse_document(wikitext, constructs).nodes;
+        let serialized = to_wikitext(&nodes);
+        let reparsed = parse_document(&serialized, constructs).nodes;
+        assert_eq!(
+            canonical(&reparsed),
+            canonical(&nodes),
+            "round trip changed the nodes, serialized as:\n{serialized}"
+        );
+        // Canonical wikitext is a fixed point
+        assert_eq!(to_wikitext(&reparsed), serialized);
+    }
+
+    #[test]
+    fn test_fixture_round_trip() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures");
+        let mut count = 0;
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let wikitext = fs::read_to_string(&path).unwrap();
+            // Without all constructs, dropped nodes leave behind whitespace the output can't keep
+            assert_round_trip(&wikitext, &Constructs::all());
+            count += 1;
+        }
+        assert!(count > 0);
+    }
+
+    #[test]
+    fn test_canonical_app_template() {
+        let wt = "{{app\n|c-characters=\n*[[cordé]] {{1st}}\n**[[Captain_Typho|Typho]]\n|l-droids=\n#[[R2-D2]]}}";
+        let nodes = parse_document(wt, &Constructs::default()).nodes;
+        assert_eq!(
+            to_wikitext(&nodes),
+            "{{App\n|c-characters=\n*[[Cordé|cordé]] {{1st}}\n**[[Captain Typho|Typho]]\n|l-droids=\n#[[R2-D2]]\n}}"
+        );
+    }
+
+    #[test]
+    fn test_escaped_text() {
+        for wt in [
+            "[[:Category:Jedi]] &lt;br&gt; &amp;amp; l&apos;''italic''",
+            "{{!}} a&b",
+            "'''''both''''' and ''just italic''",
+            "{{C|a{{!}}b|x=a{{=}}b|c{{=}}d}} [[Yoda|a{{!}}b]] [[File:Yoda.png|a{{!}}b]]",
+            "<nowiki>[[Yoda]] {{C}}</nowiki> {{C|<nowiki>}}</nowiki>}} {{C|<nowiki>{</nowiki>}}",
+        ] {
+            assert_round_trip(wt, &Constructs::all());
+        }
+    }
+
+    #[test]
+    fn test_tag_attributes() {
+        let wt = "A<ref name=\"x\" group=note>B</ref><ref name=\"x\" /><span style=\"color: red\">C</span><br />";
+        let nodes = parse_document(wt, &Constructs::all()).nodes;
+        assert_eq!(
+            to_wikitext(&nodes),
+            "A<ref name=\"x\" group=note>B</ref><ref name=\"x\"></ref><span style=\"color: red\">C</span><br>"
+        );
+        assert_round_trip(wt, &Constructs::all());
+    }
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+use crate::preprocess::SourceMap;
+
+/// Location of a node in the original wikitext.
+/// `start` and `end` are byte offsets, `line` and `column` are 1-based and point at `start`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Line start offsets of a wikitext, used to turn byte offsets into spans.
+pub struct LineIndex<'a, 'm> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+    /// Maps offsets of the preprocessed wikitext that was parsed back to `text`.
+    source_map: Option<&'m SourceMap>,
+}
+
+impl<'a, 'm> LineIndex<'a, 'm> {
+    pub fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex {
+            text,
+            line_starts,
+            source_map: None,
+        }
+    }
+
+    /// Index of `text`, for spans given as offsets of its preprocessed version.
+    pub fn with_source_map(text: &'a str, source_map: &'m SourceMap) -> Self {
+        LineIndex {
+            source_map: Some(source_map),
+            ..LineIndex::new(text)
+        }
+    }
+
+    /// The original wikitext.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Offset in the original wikitext of an offset of the parsed one.
+    pub fn to_source(&self, offset: usize) -> usize {
+        match self.source_map {
+            Some(source_map) => source_map.to_source(offset),
+            None => offset,
+        }
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        let end = match self.source_map {
+            Some(source_map) if end > start => source_map.end_to_source(end),
+            _ => self.to_source(end),
+        };
+        let start = self.to_source(start);
+        let line = self
+            .line_starts
+            .partition_point(|&line_start| line_start <= start);
+        let line_start = self.line_starts[line - 1];
+        // Columns count characters, not bytes, so they match what an editor shows.
+        let column = self
+            .text
+            .get(line_start..start)
+            .map_or(start - line_start, |prefix| prefix.chars().count())
+            + 1;
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preprocess::preprocess;
+
+    #[test]
+    fn test_line_and_column() {
+        let lines = LineIndex::new("{{App\n|c-characters=\n*[[Cordé]] {{1st}}");
+        assert_eq!(
+            lines.span(0, 5),
+            Span {
+                start: 0,
+                end: 5,
+                line: 1,
+                column: 1
+            }
+        );
+        assert_eq!(
+            lines.span(21, 32),
+            Span {
+                start: 21,
+                end: 32,
+                line: 3,
+                column: 1
+            }
+        );
+        // "é" is two bytes but one column
+        assert_eq!(
+            lines.span(33, 40),
+            Span {
+                start: 33,
+                end: 40,
+                line: 3,
+                column: 12
+            }
+        );
+    }
+
+    #[test]
+    fn test_source_map() {
+        let wt = "<!-- Top -->\n*[[Cordé]]{{!}}";
+        let preprocessed = preprocess(wt, false);
+        let lines = LineIndex::with_source_map(wt, &preprocessed.source_map);
+        let span = lines.span(0, preprocessed.text.len());
+        assert_eq!((span.start, span.end, span.line), (13, wt.len(), 2));
+
+        // The end of a link followed by a removed comment stays before the comment
+        let wt = "[[Cordé]]<!-- é --> {{1st}}";
+        let preprocessed = preprocess(wt, false);
+        let lines = LineIndex::with_source_map(wt, &preprocessed.source_map);
+        let span = lines.span(0, preprocessed.text.find(' ').unwrap());
+        assert_eq!(&wt[span.start..span.end], "[[Cordé]]");
+    }
+}
@@ -0,0 +1,341 @@
+//! Internally tagged serialization of nodes, e.g. `{ "type": "internal link", "page": "Yoda", ... }`,
+//! in the shape of the TypeScript `AstNode`s used for infobox fields. Nodes are serialized in serde's
+//! externally tagged form, e.g. `{ "Link": { "target": "Yoda", ... } }`, unless `with_node_format`
+//! asks for the tagged one. Only the externally tagged form can be deserialized.
+
+use std::cell::Cell;
+
+use serde::{Deserialize, Serialize};
+
+use crate::span::Span;
+use crate::{
+    simple_nodes_to_text, SimpleDefinitionItem, SimpleNode, SimpleParameter, SimpleTableRow,
+};
+
+#[derive(Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub enum NodeFormat {
+    /// `{ "Link": { ... } }`
+    #[default]
+    External,
+    /// `{ "type": "internal link", ... }`
+    Tagged,
+}
+
+thread_local! {
+    static FORMAT: Cell<NodeFormat> = const { Cell::new(NodeFormat::External) };
+}
+
+/// Runs `f`, typically a serialization, with the nodes it serializes on this thread in `format`.
+pub fn with_node_format<T>(format: NodeFormat, f: impl FnOnce() -> T) -> T {
+    /// Restores the previous format even if `f` panics.
+    struct Restore(NodeFormat);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FORMAT.with(|current| current.set(self.0));
+        }
+    }
+
+    let _restore = Restore(FORMAT.with(|current| current.replace(format)));
+    f()
+}
+
+pub(crate) fn node_format() -> NodeFormat {
+    FORMAT.with(Cell::get)
+}
+
+/// Tagged view of a `SimpleNode`. Nested nodes are serialized through `SimpleNode`, so they're tagged too.
+#[derive(Serialize)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(
+    tag = "type",
+    rename_all = "lowercase",
+    rename_all_fields = "camelCase"
+)]
+pub enum TaggedNode<'a> {
+    List {
+        ordered: bool,
+        #[cfg_attr(feature = "ts", ts(type = "Array<Array<TaggedNode>>"))]
+        data: &'a [Vec<SimpleNode<'a>>],
+        span: Span,
+    },
+    #[serde(rename = "definition list")]
+    DefinitionList {
+        #[cfg_attr(feature = "ts", ts(type = "Array<Tagged<SimpleDefinitionItem>>"))]
+        items: &'a [SimpleDefinitionItem<'a>],
+        span: Span,
+    },
+    Template {
+        name: &'a str,
+        #[cfg_attr(feature = "ts", ts(type = "Array<Tagged<SimpleParameter>>"))]
+        parameters: &'a [SimpleParameter<'a>],
+        span: Span,
+    },
+    #[serde(rename = "internal link")]
+    InternalLink {
+        page: &'a str,
+        text: &'a str,
+        span: Span,
+    },
+    /// `text` is the plain text of `nodes`.
+    #[serde(rename = "external link")]
+    ExternalLink {
+        site: &'a str,
+        text: String,
+        #[cfg_attr(feature = "ts", ts(type = "Array<TaggedNode>"))]
+        nodes: &'a [SimpleNode<'a>],
+        span: Span,
+    },
+    Image {
+        target: &'a str,
+        #[cfg_attr(feature = "ts", ts(type = "Array<TaggedNode>"))]
+        text: &'a [SimpleNode<'a>],
+        span: Span,
+    },
+    Category {
+        target: &'a str,
+        sort_key: Option<&'a str>,
+        span: Span,
+    },
+    Text {
+        text: &'a str,
+        span: Span,
+    },
+    Bold {
+        span: Span,
+    },
+    Italic {
+        span: Span,
+    },
+    #[serde(rename = "bold italic")]
+    BoldItalic {
+        span: Span,
+    },
+    Heading {
+        level: u8,
+        #[cfg_attr(feature = "ts", ts(type = "Array<TaggedNode>"))]
+        nodes: &'a [SimpleNode<'a>],
+        span: Span,
+    },
+    Tag {
+        name: &'a str,
+        attributes: &'a str,
+        #[cfg_attr(feature = "ts", ts(type = "Array<TaggedNode>"))]
+        nodes: &'a [SimpleNode<'a>],
+        span: Span,
+    },
+    #[serde(rename = "start tag")]
+    StartTag {
+        name: &'a str,
+        attributes: &'a str,
+        span: Span,
+    },
+    #[serde(rename = "end tag")]
+    EndTag {
+        name: &'a str,
+        span: Span,
+    },
+    Table {
+        attributes: &'a str,
+        #[cfg_attr(feature = "ts", ts(type = "Array<Array<TaggedNode>>"))]
+        captions: &'a [Vec<SimpleNode<'a>>],
+        #[cfg_attr(feature = "ts", ts(type = "Array<Tagged<SimpleTableRow>>"))]
+        rows: &'a [SimpleTableRow<'a>],
+        span: Span,
+    },
+    Preformatted {
+        #[cfg_attr(feature = "ts", ts(type = "Array<TaggedNode>"))]
+        nodes: &'a [SimpleNode<'a>],
+        span: Span,
+    },
+    Parameter {
+        name: &'a str,
+        #[cfg_attr(feature = "ts", ts(type = "Array<TaggedNode> | null"))]
+        default: Option<&'a [SimpleNode<'a>]>,
+        span: Span,
+    },
+    #[serde(rename = "magic word")]
+    MagicWord {
+        name: &'a str,
+        span: Span,
+    },
+    Redirect {
+        target: &'a str,
+        span: Span,
+    },
+    Comment {
+        text: &'a str,
+        span: Span,
+    },
+    #[serde(rename = "horizontal divider")]
+    HorizontalDivider {
+        span: Span,
+    },
+    #[serde(rename = "paragraph break")]
+    ParagraphBreak {
+        span: Span,
+    },
+}
+
+impl<'a> From<&'a SimpleNode<'a>> for TaggedNode<'a> {
+    fn from(node: &'a SimpleNode<'a>) -> Self {
+        match node {
+            SimpleNode::List {
+                ordered,
+                items,
+                span,
+            } => TaggedNode::List {
+                ordered: *ordered,
+                data: items,
+                span: *span,
+            },
+            SimpleNode::DefinitionList { items, span } => {
+                TaggedNode::DefinitionList { items, span: *span }
+            }
+            SimpleNode::Template(template) => TaggedNode::Template {
+                name: &template.name,
+                parameters: &template.parameters,
+                span: template.span,
+            },
+            SimpleNode::Link { target, text, span } => TaggedNode::InternalLink {
+                page: target,
+                text,
+                span: *span,
+            },
+            SimpleNode::ExternalLink { url, text, span } => TaggedNode::ExternalLink {
+                site: url,
+                text: simple_nodes_to_text(text),
+                nodes: text,
+                span: *span,
+            },
+            SimpleNode::Image { target, text, span } => TaggedNode::Image {
+                target,
+                text,
+                span: *span,
+            },
+            SimpleNode::Category {
+                target,
+                sort_key,
+                span,
+            } => TaggedNode::Category {
+                target,
+                sort_key: sort_key.as_deref(),
+                span: *span,
+            },
+            SimpleNode::Text { value, span } => TaggedNode::Text {
+                text: value,
+                span: *span,
+            },
+            SimpleNode::Bold { span } => TaggedNode::Bold { span: *span },
+            SimpleNode::Italic { span } => TaggedNode::Italic { span: *span },
+            SimpleNode::BoldItalic { span } => TaggedNode::BoldItalic { span: *span },
+            SimpleNode::Heading { level, nodes, span } => TaggedNode::Heading {
+                level: *level,
+                nodes,
+                span: *span,
+            },
+            SimpleNode::Tag {
+                name,
+                attributes,
+                nodes,
+                span,
+            } => TaggedNode::Tag {
+                name,
+                attributes,
+                nodes,
+                span: *span,
+            },
+            SimpleNode::StartTag {
+                name,
+                attributes,
+                span,
+            } => TaggedNode::StartTag {
+                name,
+                attributes,
+                span: *span,
+            },
+            SimpleNode::EndTag { name, span } => TaggedNode::EndTag { name, span: *span },
+            SimpleNode::Table(table) => TaggedNode::Table {
+                attributes: &table.attributes,
+                captions: &table.captions,
+                rows: &table.rows,
+                span: table.span,
+            },
+            SimpleNode::Preformatted { nodes, span } => {
+                TaggedNode::Preformatted { nodes, span: *span }
+            }
+            SimpleNode::Parameter {
+                name,
+                default,
+                span,
+            } => TaggedNode::Parameter {
+                name,
+                default: default.as_deref(),
+                span: *span,
+            },
+            SimpleNode::MagicWord { name, span } => TaggedNode::MagicWord { name, span: *span },
+            SimpleNode::Redirect { target, span } => TaggedNode::Redirect {
+                target,
+                span: *span,
+            },
+            SimpleNode::Comment { text, span } => TaggedNode::Comment { text, span: *span },
+            SimpleNode::HorizontalDivider { span } => TaggedNode::HorizontalDivider { span: *span },
+            SimpleNode::ParagraphBreak { span } => TaggedNode::ParagraphBreak { span: *span },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::appearances::parse_appearances;
+    use crate::constructs::Constructs;
+    use crate::options::ParseOptions;
+    use crate::parse_document;
+
+    #[test]
+    fn test_tagged_nodes() {
+        let wt = "*[[Yoda|Master Yoda]] [https://starwars.com ''Official'' site]";
+        let parsed = parse_document(wt, &Constructs::all());
+        let value =
+            with_node_format(NodeFormat::Tagged, || serde_json::to_value(&parsed.nodes)).unwrap();
+        let list = &value[0];
+        assert_eq!(list["type"], "list");
+        assert_eq!(list["ordered"], false);
+        let items = &list["data"][0];
+        assert_eq!(items[0]["type"], "internal link");
+        assert_eq!(items[0]["page"], "Yoda");
+        assert_eq!(items[0]["text"], "Master Yoda");
+        assert_eq!(items[2]["type"], "external link");
+        assert_eq!(items[2]["site"], "https://starwars.com");
+        assert_eq!(items[2]["text"], "Official site");
+        assert_eq!(
+            items[2]["nodes"][0],
+            json!({ "type": "italic", "span": items[2]["nodes"][0]["span"] })
+        );
+
+        // The format only applies inside `with_node_format`
+        let value = serde_json::to_value(&parsed.nodes).unwrap();
+        assert!(value[0]["List"].is_object());
+    }
+
+    #[test]
+    fn test_tagged_appearances() {
+        let wt = "{{App\n|c-characters=\n*[[Dooku]] {{C|[[Hologram]] only}}\n}}";
+        let appearances = parse_appearances(wt, &ParseOptions::default()).unwrap();
+        let value =
+            with_node_format(NodeFormat::Tagged, || serde_json::to_value(&appearances)).unwrap();
+        let template = &value["nodes"][0];
+        assert_eq!(template["type"], "template");
+        assert_eq!(template["name"], "App");
+        let entry = &template["parameters"][0]["value"][0]["data"][0];
+        assert_eq!(entry[0]["page"], "Dooku");
+        // Templates of appearances are tagged too
+        let comment = &value["links"]["c-characters"][0]["templates"][0]["parameters"][0];
+        assert_eq!(comment["value"][0]["type"], "internal link");
+    }
+}
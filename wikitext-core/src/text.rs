@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::title::normalize_link_target;
+use crate::{SimpleNode, SimpleTemplate};
+
+/// Default template expansions, see `TemplateRule`.
+const DEFAULT_RULES: &str = include_str!("../text_rules.json");
+
+/// How a template is rendered as plain text. Templates without a rule are dropped.
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(tag = "rule", rename_all = "camelCase")]
+pub enum TemplateRule {
+    /// The first of `params` with a non-empty value.
+    Param {
+        params: Vec<ParamRef>,
+        /// Parameters naming the page the template links to, e.g. `story` of `{{StoryCite}}`.
+        #[serde(default)]
+        link: Vec<ParamKey>,
+    },
+    Literal {
+        text: String,
+    },
+    Drop,
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(untagged)]
+pub enum ParamKey {
+    /// 1-based position among the unnamed parameters.
+    Position(usize),
+    Name(String),
+}
+
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(untagged, rename_all_fields = "camelCase")]
+pub enum ParamRef {
+    Key(ParamKey),
+    Detailed {
+        param: ParamKey,
+        /// Drops a trailing disambiguation like ` (novel)`, as `{{HideParanthetical}}` does for page names.
+        #[serde(default)]
+        hide_parenthetical: bool,
+    },
+}
+
+/// Template expansion rules keyed by lowercase template name.
+#[derive(Deserialize, Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(transparent)]
+pub struct TextRules(HashMap<String, TemplateRule>);
+
+impl Default for TextRules {
+    fn default() -> Self {
+        serde_json::from_str(DEFAULT_RULES).expect("text_rules.json should be valid")
+    }
+}
+
+impl TextRules {
+    /// Default rules with `overrides` replacing or adding to them.
+    pub fn with_overrides(overrides: TextRules) -> Self {
+        let mut rules = Self::default();
+        rules.0.extend(overrides.0);
+        rules
+    }
+
+    fn get(&self, template: &str) -> Option<&TemplateRule> {
+        self.0.get(&template.trim().to_lowercase())
+    }
+}
+
+/// Readable text of `nodes`: link text, expanded templates and list items on their own lines.
+/// Formatting, references, comments, images, categories and tables are left out.
+pub fn render_text(nodes: &[SimpleNode], rules: &TextRules) -> String {
+    let mut text = String::new();
+    for node in nodes {
+        match node {
+            SimpleNode::Text { value, .. } => text.push_str(value),
+            SimpleNode::Link {
+                text: link_text, ..
+            } => text.push_str(link_text),
+            SimpleNode::ExternalLink {
+                url,
+                text: link_text,
+                ..
+            } => {
+                if link_text.is_empty() {
+                    text.push_str(url);
+                } else {
+                    text.push_str(&render_text(link_text, rules));
+                }
+            }
+            SimpleNode::Template(template) => text.push_str(&render_template(template, rules)),
+            SimpleNode::List { items, .. } => {
+                push_lines(&mut text, items.iter().map(|item| render_text(item, rules)))
+            }
+            SimpleNode::DefinitionList { items, .. } => push_lines(
+                &mut text,
+                items.iter().map(|item| render_text(&item.nodes, rules)),
+            ),
+            SimpleNode::Heading { nodes, .. } => {
+                push_lines(&mut text, std::iter::once(render_text(nodes, rules)))
+            }
+            SimpleNode::Tag { name, .. } if name == "ref" => (),
+            SimpleNode::Tag { nodes, .. } | SimpleNode::Preformatted { nodes, .. } => {
+                text.push_str(&render_text(nodes, rules))
+            }
+            SimpleNode::StartTag { name, .. } if name == "br" => text.push('\n'),
+            SimpleNode::Parameter {
+                default: Some(default),
+                ..
+            } => text.push_str(&render_text(default, rules)),
+            SimpleNode::ParagraphBreak { .. } => text.push_str("\n\n"),
+            _ => (),
+        }
+    }
+    text
+}
+
+fn render_template(template: &SimpleTemplate, rules: &TextRules) -> String {
+    match rules.get(&template.name) {
+        Some(TemplateRule::Param { params, .. }) => params
+            .iter()
+            .find_map(|param| {
+                let (key, hide_parenthetical) = match param {
+                    ParamRef::Key(key) => (key, false),
+                    ParamRef::Detailed {
+                        param,
+                        hide_parenthetical,
+                    } => (param, *hide_parenthetical),
+                };
+                let value = render_text(param_value(template, key)?, rules);
+                let value = value.trim();
+                let value = if hide_parenthetical {
+                    hide_parenthetical_suffix(value)
+                } else {
+                    value
+                };
+                (!value.is_empty()).then(|| value.to_string())
+            })
+            .unwrap_or_default(),
+        Some(TemplateRule::Literal { text }) => text.clone(),
+        Some(TemplateRule::Drop) | None => String::new(),
+    }
+}
+
+/// Page the nodes link to first, either with a link or with a template that has `link` parameters.
+pub fn link_target(nodes: &[SimpleNode], rules: &TextRules) -> Option<String> {
+    nodes.iter().find_map(|node| match node {
+        SimpleNode::Link { target, .. } => Some(target.to_string()),
+        SimpleNode::Template(template) => match rules.get(&template.name) {
+            Some(TemplateRule::Param { link, .. }) => link.iter().find_map(|key| {
+                let target = render_text(param_value(template, key)?, rules);
+                let target = target.trim();
+                (!target.is_empty()).then(|| normalize_link_target(target).into_owned())
+            }),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+fn param_value<'a, 'b>(
+    template: &'a SimpleTemplate<'b>,
+    key: &ParamKey,
+) -> Option<&'a [SimpleNode<'b>]> {
+    let param = match key {
+        ParamKey::Position(position) => template
+            .parameters
+            .iter()
+            .filter(|param| param.name.is_none())
+            .nth(position.checked_sub(1)?),
+        ParamKey::Name(name) => template
+            .parameters
+            .iter()
+            .find(|param| param.name.as_deref().map(str::trim) == Some(name.as_str())),
+    }?;
+    Some(&param.value)
+}
+
+fn hide_parenthetical_suffix(title: &str) -> &str {
+    match title.rfind('(') {
+        Some(index) if index > 0 && title.ends_with(')') => title[..index].trim_end(),
+        _ => title,
+    }
+}
+
+/// Puts every line on its own, so lists don't run into the surrounding text.
+fn push_lines(text: &mut String, lines: impl Iterator<Item = String>) {
+    for line in lines {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+        text.push_str(line.trim());
+    }
+    text.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constructs::Constructs;
+    use crate::parse_document;
+
+    fn render(wikitext: &str) -> String {
+        let parsed = parse_document(wikitext, &Constructs::all());
+        render_text(&parsed.nodes, &TextRules::default())
+    }
+
+    #[test]
+    fn test_template_rules() {
+        assert_eq!(
+            render("{{StoryCite|book=Tales|story=Kenobi's Journey (short story)}}"),
+            "Kenobi's Journey"
+        );
+        assert_eq!(
+            render("{{StoryCite|story=Kenobi's Journey (short story)|stext=Journey}}"),
+            "Journey"
+        );
+        assert_eq!(
+            render("{{TCW|Destiny (Star Wars: The Clone Wars)|Destiny}}"),
+            "Destiny"
+        );
+        assert_eq!(render("[[Yoda]]{{'s}} hut{{1st}}"), "Yoda's hut");
+        // Only the trailing parenthetical is hidden
+        assert_eq!(
+            render("{{StoryCite|story=Star Wars (1977 film) (novel)}}"),
+            "Star Wars (1977 film)"
+        );
+    }
+
+    #[test]
+    fn test_link_target() {
+        let rules = TextRules::default();
+        let parsed = parse_document(
+            "{{StoryCite|book=Tales|story=Kenobi's_Journey (short story)}} ''[[Tales]]''",
+            &Constructs::all(),
+        );
+        assert_eq!(
+            link_target(&parsed.nodes, &rules).as_deref(),
+            Some("Kenobi's Journey (short story)")
+        );
+        let parsed = parse_document("{{1st}} [[Yoda]]", &Constructs::default());
+        assert_eq!(link_target(&parsed.nodes, &rules).as_deref(), Some("Yoda"));
+    }
+
+    #[test]
+    fn test_overrides() {
+        let overrides: TextRules =
+            serde_json::from_str(r#"{ "1st": { "rule": "literal", "text": " (first)" } }"#)
+                .unwrap();
+        let rules = TextRules::with_overrides(overrides);
+        let parsed = parse_document("[[Yoda]]{{1st}}{{'s}}", &Constructs::default());
+        assert_eq!(render_text(&parsed.nodes, &rules), "Yoda (first)'s");
+    }
+
+    #[test]
+    fn test_skipped_constructs() {
+        assert_eq!(
+            render("''[[Dark Disciple]]''<ref>{{Cite web}}</ref> by [https://example.com Christie Golden]<!-- TODO -->"),
+            "Dark Disciple by Christie Golden"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use serde::Serialize;
+
+use crate::constructs::Constructs;
+use crate::diagnostics::Diagnostic;
+use crate::text::{link_target, render_text, TextRules};
+use crate::title::normalize_title;
+use crate::{parse_document, Error, Result, SimpleNode, SimpleTable, Span};
+
+pub(crate) const HEADER: [&str; 4] = ["Year", "", "Title", "Released"];
+
+/// Marks entries whose exact place in the timeline is unknown.
+const PLACEMENT_UNKNOWN: char = '†';
+
+/// Row of the "Timeline of canon media" table.
+#[derive(Serialize, Debug)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineRow<'a> {
+    /// In-universe date, e.g. `19 BBY`.
+    pub year: String,
+    /// Media type code from the unnamed column, e.g. `N` or `TV`.
+    pub type_code: String,
+    /// Page the title links to, from its first link or citation template, without the anchor.
+    pub title_link: Option<String>,
+    /// Text of the title cell before the notes, without the `†` flag.
+    pub title_text: String,
+    pub title_nodes: Vec<SimpleNode<'a>>,
+    /// Notes following `*` in the title cell, e.g. `Adaptation`.
+    pub notes: Vec<String>,
+    pub exact_placement_unknown: bool,
+    pub release_date: String,
+    pub span: Span,
+}
+
+/// The timeline is the first top-level table with a `Year | | Title | Released` header.
+pub fn find_timeline_table<'a, 'b>(
+    nodes: &'a [SimpleNode<'b>],
+    rules: &TextRules,
+) -> Option<&'a SimpleTable<'b>> {
+    nodes.iter().find_map(|node| match node {
+        SimpleNode::Table(table)
+            if table.rows.first().is_some_and(|header| {
+                header.cells.len() == HEADER.len()
+                    && header
+                        .cells
+                        .iter()
+                        .zip(HEADER)
+                        .all(|(cell, name)| render_text(&cell.content, rules).trim() == name)
+            }) =>
+        {
+            Some(table)
+        }
+        _ => None,
+    })
+}
+
+pub fn timeline_rows<'a>(
+    table: &SimpleTable<'a>,
+    rules: &TextRules,
+) -> Result<Vec<TimelineRow<'a>>> {
+    table.rows[1..]
+        .iter()
+        .map(|row| {
+            let [year, type_code, title, release_date] = &row.cells[..] else {
+                return Err(Error::TimelineRow {
+                    line: row.span.line,
+                    cells: row.cells.len(),
+                });
+            };
+            let cell_text = |nodes: &[SimpleNode]| render_text(nodes, rules).trim().to_string();
+            let title_cell = cell_text(&title.content);
+            let mut parts = title_cell.split('*');
+            let title_text = parts.next().unwrap_or_default();
+            Ok(TimelineRow {
+                year: cell_text(&year.content),
+                type_code: cell_text(&type_code.content),
+                title_link: link_target(&title.content, rules)
+                    .map(|target| normalize_title(&target).into_owned()),
+                title_text: title_text.replace(PLACEMENT_UNKNOWN, "").trim().to_string(),
+                title_nodes: title.content.clone(),
+                notes: parts
+                    .map(str::trim)
+                    .filter(|note| !note.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                exact_placement_unknown: title_cell.contains(PLACEMENT_UNKNOWN),
+                release_date: cell_text(&release_date.content),
+                span: row.span,
+            })
+        })
+        .collect()
+}
+
+#[derive(Serialize, Debug)]
+#[cfg_attr(feature = "ts", derive(ts_rs::TS))]
+pub struct ParsedTimeline<'a> {
+    pub rows: Vec<TimelineRow<'a>>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Rows of the timeline table of a page. Parsing is lenient, diagnostics are returned next to the rows.
+pub fn parse_timeline<'a>(wikitext: &'a str, rules: &TextRules) -> Result<ParsedTimeline<'a>> {
+    let parsed = parse_document(wikitext, &Constructs::all());
+    let table = find_timeline_table(&parsed.nodes, rules).ok_or(Error::TimelineNotFound)?;
+    Ok(ParsedTimeline {
+        rows: timeline_rows(table, rules)?,
+        diagnostics: parsed.diagnostics,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::constructs::Constructs;
+    use crate::parse_document;
+
+    #[test]
+    fn test_timeline_rows() {
+        let wt = fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/timeline-excerpt.wiki"
+        ))
+        .unwrap();
+        let rules = TextRules::default();
+        let parsed = parse_document(&wt, &Constructs::all());
+        let table = find_timeline_table(&parsed.nodes, &rules).unwrap();
+        let rows = timeline_rows(table, &rules).unwrap();
+        assert_eq!(rows.len(), 6);
+
+        assert_eq!(rows[0].year, "232 BBY");
+        assert_eq!(rows[0].type_code, "N");
+        assert_eq!(
+            rows[0].title_link.as_deref(),
+            Some("The High Republic: Convergence")
+        );
+        assert_eq!(rows[0].title_text, "Convergence");
+
+        assert_eq!(
+            rows[1].title_link.as_deref(),
+            Some("The High Republic Adventures 1 (2022)")
+        );
+        assert_eq!(rows[1].title_text, "The High Republic Adventures 1");
+        assert_eq!(rows[2].title_text, "Destiny");
+        assert_eq!(rows[2].release_date, "2011-02-25");
+
+        assert_eq!(rows[3].notes, ["Adaptation"]);
+        assert!(rows[3].exact_placement_unknown);
+        assert_eq!(rows[3].title_text, "Dark Disciple");
+        assert!(!rows[0].exact_placement_unknown);
+        assert!(rows[4].title_link.is_none());
+        // Links to a section of the page are rows of the page itself
+        assert_eq!(
+            rows[5].title_link.as_deref(),
+            Some("Star Wars: The Clone Wars (film)")
+        );
+    }
+
+    #[test]
+    fn test_wrong_cell_count() {
+        let wt = "{|\n!Year!!!!Title!!Released\n|-\n|19 BBY||N||[[Dark Disciple]]\n|}";
+        let rules = TextRules::default();
+        let parsed = parse_document(wt, &Constructs::all());
+        let table = find_timeline_table(&parsed.nodes, &rules).unwrap();
+        let error = timeline_rows(table, &rules).unwrap_err();
+        assert!(matches!(error, Error::TimelineRow { line: 3, cells: 3 }));
+        assert!(error.to_string().contains("line 3"), "{error}");
+    }
+
+    #[test]
+    fn test_timeline_not_found() {
+        let error = parse_timeline("{|\n!Year!!Title\n|}", &TextRules::default()).unwrap_err();
+        assert!(matches!(error, Error::TimelineNotFound));
+    }
+}
@@ -0,0 +1,228 @@
+//! Page title normalization following MediaWiki rules, so that `Battle_of_Endor`, `battle of Endor`
+//! and `Battle of  Endor` all refer to the same page.
+
+use std::borrow::Cow;
+
+use html_escape::decode_html_entities;
+
+/// Namespaces in use on Wookieepedia. Aliases are mapped to their canonical name.
+const NAMESPACES: &[(&str, &str)] = &[
+    ("talk", "Talk"),
+    ("user", "User"),
+    ("user talk", "User talk"),
+    ("wookieepedia", "Wookieepedia"),
+    ("project", "Wookieepedia"),
+    ("wookieepedia talk", "Wookieepedia talk"),
+    ("file", "File"),
+    ("image", "File"),
+    ("file talk", "File talk"),
+    ("mediawiki", "MediaWiki"),
+    ("template", "Template"),
+    ("template talk", "Template talk"),
+    ("help", "Help"),
+    ("category", "Category"),
+    ("category talk", "Category talk"),
+    ("forum", "Forum"),
+    ("module", "Module"),
+];
+
+const TEMPLATE_NAMESPACE: &str = "Template";
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Title {
+    /// Canonical namespace name, `None` for the main namespace.
+    pub namespace: Option<String>,
+    /// Page name without the namespace.
+    pub name: String,
+    /// Section the title points to, the part after `#`.
+    pub anchor: Option<String>,
+}
+
+impl Title {
+    pub fn parse(raw: &str) -> Self {
+        let decoded = decode_html_entities(raw);
+        let (page, anchor) = match decoded.split_once('#') {
+            Some((page, anchor)) => (page, Some(anchor)),
+            None => (decoded.as_ref(), None),
+        };
+        let page = collapse_whitespace(page);
+        // A leading colon forces a link to a namespace page instead of e.g. categorizing
+        let page = page
+            .strip_prefix(':')
+            .map_or(page.as_str(), str::trim_start);
+        let (namespace, name) = match page.split_once(':') {
+            Some((prefix, name)) => match canonical_namespace(prefix) {
+                Some(namespace) => (Some(namespace.to_string()), name.trim_start()),
+                None => (None, page),
+            },
+            None => (None, page),
+        };
+        Title {
+            namespace,
+            name: capitalize_first(name),
+            anchor: anchor
+                .map(collapse_whitespace)
+                .filter(|anchor| !anchor.is_empty()),
+        }
+    }
+
+    /// Namespace and name, without the anchor.
+    pub fn page(&self) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("{namespace}:{}", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Namespace, name and anchor.
+    pub fn full(&self) -> String {
+        match &self.anchor {
+            Some(anchor) => format!("{}#{anchor}", self.page()),
+            None => self.page(),
+        }
+    }
+}
+
+/// Normalizes a page title and drops its anchor.
+pub fn normalize_title(raw: &str) -> Cow<'_, str> {
+    if is_normalized(raw) {
+        return Cow::Borrowed(raw);
+    }
+    Cow::Owned(Title::parse(raw).page())
+}
+
+/// Normalizes a link target, keeping its anchor.
+pub fn normalize_link_target(raw: &str) -> Cow<'_, str> {
+    if is_normalized(raw) {
+        return Cow::Borrowed(raw);
+    }
+    Cow::Owned(Title::parse(raw).full())
+}
+
+/// Normalizes a template name, dropping the implied `Template:` namespace.
+/// Parser functions like `#if:` are only trimmed.
+pub fn normalize_template_name(raw: &str) -> Cow<'_, str> {
+    let trimmed = raw.trim();
+    if trimmed.starts_with('#') || is_normalized(trimmed) {
+        return Cow::Borrowed(trimmed);
+    }
+    let title = Title::parse(trimmed);
+    Cow::Owned(match title.namespace.as_deref() {
+        Some(TEMPLATE_NAMESPACE) => title.name,
+        _ => title.page(),
+    })
+}
+
+/// Whether `raw` is a main namespace title that `Title::parse` would leave as it is,
+/// so the common case doesn't allocate.
+fn is_normalized(raw: &str) -> bool {
+    let Some(first) = raw.chars().next() else {
+        return true;
+    };
+    first.to_uppercase().eq([first])
+        && !raw.starts_with(' ')
+        && !raw.ends_with(' ')
+        && !raw.contains("  ")
+        && !raw.contains(|c: char| {
+            matches!(c, '_' | '&' | '#' | ':') || (c.is_whitespace() && c != ' ')
+        })
+}
+
+fn canonical_namespace(prefix: &str) -> Option<&'static str> {
+    let prefix = prefix.trim().to_lowercase();
+    NAMESPACES
+        .iter()
+        .find(|(alias, _)| *alias == prefix)
+        .map(|(_, canonical)| *canonical)
+}
+
+/// Turns underscores into spaces, collapses runs of whitespace and trims.
+fn collapse_whitespace(text: &str) -> String {
+    text.split(|c: char| c == '_' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn capitalize_first(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equivalent_spellings() {
+        for raw in [
+            "Battle of Endor",
+            "Battle_of_Endor",
+            "battle of Endor",
+            " Battle  of_Endor ",
+            "Battle&#32;of&nbsp;Endor",
+        ] {
+            assert_eq!(normalize_title(raw), "Battle of Endor", "{raw:?}");
+        }
+    }
+
+    #[test]
+    fn test_namespaces() {
+        assert_eq!(normalize_title("image:Yoda.jpg"), "File:Yoda.jpg");
+        assert_eq!(
+            normalize_title(":category:Jedi_Masters"),
+            "Category:Jedi Masters"
+        );
+        assert_eq!(normalize_title("Star Wars: Andor"), "Star Wars: Andor");
+        assert_eq!(
+            Title::parse("Template_talk:app"),
+            Title {
+                namespace: Some("Template talk".to_string()),
+                name: "App".to_string(),
+                anchor: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_anchors() {
+        let title = Title::parse("coruscant#Senate_District");
+        assert_eq!(title.page(), "Coruscant");
+        assert_eq!(title.anchor.as_deref(), Some("Senate District"));
+        assert_eq!(
+            normalize_link_target("coruscant#Senate_District"),
+            "Coruscant#Senate District"
+        );
+        assert_eq!(normalize_link_target("Coruscant#"), "Coruscant");
+    }
+
+    #[test]
+    fn test_template_names() {
+        assert_eq!(normalize_template_name("app\n"), "App");
+        assert_eq!(normalize_template_name("Template:1stID"), "1stID");
+        assert_eq!(normalize_template_name("StoryCite"), "StoryCite");
+        assert_eq!(normalize_template_name("#if: {{{1}}}"), "#if: {{{1}}}");
+    }
+
+    #[test]
+    fn test_normalized_titles_are_borrowed() {
+        for raw in ["Battle of Endor", "1stID", "Ahsoka Tano", ""] {
+            assert!(matches!(normalize_title(raw), Cow::Borrowed(_)), "{raw:?}");
+        }
+        for raw in [
+            "battle of Endor",
+            "Battle_of_Endor",
+            "Battle  of Endor",
+            "Image:Yoda.jpg",
+        ] {
+            assert!(matches!(normalize_title(raw), Cow::Owned(_)), "{raw:?}");
+        }
+        assert!(matches!(
+            normalize_template_name("App\n"),
+            Cow::Borrowed("App")
+        ));
+    }
+}